@@ -0,0 +1,97 @@
+//! Implementations of the spec's abstract operations.
+//! These are shared between built-in functions and operators.
+
+use num::{BigInt, Num, ToPrimitive};
+
+use crate::util::{is_line_terminator, is_whitespace, NumberLiteralBase};
+
+/// The spec's `StringToNumber` abstract operation.  
+/// Converts a string to a number, as used by `Number(s)`, unary `+`, and loose equality.  
+/// Returns `NaN` if the string is not a valid `StringNumericLiteral`.
+pub fn string_to_number(s: &str) -> f64 {
+    // Leading and trailing whitespace and line terminators are ignored
+    let s = s.trim_matches(|c| is_whitespace(c) || is_line_terminator(c));
+
+    // An empty or whitespace-only string is 0
+    if s.is_empty() {
+        return 0.0;
+    }
+
+    // Non-decimal integer literals, which can't have a sign
+    for base in [NumberLiteralBase::Binary, NumberLiteralBase::Octal, NumberLiteralBase::Hex] {
+        let start = base.get_start();
+        match s.get(..start.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(start) => (),
+            _ => continue,
+        }
+        let digits = &s[start.len()..];
+
+        if digits.is_empty() || !digits.chars().all(|c| base.get_chars().contains(c)) {
+            return f64::NAN;
+        }
+
+        let n = BigInt::from_str_radix(digits, base.get_radix()).expect("Should have been a valid bigint");
+        return n.to_f64().unwrap_or(f64::INFINITY);
+    }
+
+    // Decimal literals and Infinity can have a sign
+    let (sign, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    if unsigned == "Infinity" {
+        return sign * f64::INFINITY;
+    }
+
+    // Rust's float parsing accepts strings such as "inf" and "NaN", so check the characters first.
+    // Rust's parser still checks the structure of the number e.g. rejecting "1e" or "."
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.') || !unsigned.chars().all(|c| matches!(c, '0'..='9' | '.' | 'e' | 'E' | '+' | '-')) {
+        return f64::NAN;
+    }
+
+    match unsigned.parse::<f64>() {
+        Ok(n) => sign * n,
+        Err(_) => f64::NAN,
+    }
+}
+
+#[test]
+/// Tests `string_to_number` with decimal strings
+fn test_string_to_number_decimal() {
+    assert_eq!(string_to_number("12"), 12.0);
+    assert_eq!(string_to_number(" \t12\n "), 12.0);
+    assert_eq!(string_to_number("\u{FEFF}\u{00A0}1.5\u{2028}"), 1.5);
+    assert_eq!(string_to_number("-.5"), -0.5);
+    assert_eq!(string_to_number("+5."), 5.0);
+    assert_eq!(string_to_number("1e3"), 1000.0);
+    assert_eq!(string_to_number("1E-3"), 0.001);
+    assert_eq!(string_to_number(""), 0.0);
+    assert_eq!(string_to_number("   "), 0.0);
+    assert!(string_to_number("-0").is_sign_negative());
+
+    assert!(string_to_number("1_000").is_nan());
+    assert!(string_to_number("12px").is_nan());
+    assert!(string_to_number(".").is_nan());
+    assert!(string_to_number("1e").is_nan());
+    assert!(string_to_number("+-1").is_nan());
+    assert!(string_to_number("1 2").is_nan());
+}
+
+#[test]
+/// Tests `string_to_number` with `Infinity` and non-decimal strings
+fn test_string_to_number_special() {
+    assert_eq!(string_to_number("Infinity"), f64::INFINITY);
+    assert_eq!(string_to_number(" -Infinity "), f64::NEG_INFINITY);
+    assert!(string_to_number("infinity").is_nan());
+    assert!(string_to_number("inf").is_nan());
+    assert!(string_to_number("NaN").is_nan());
+
+    assert_eq!(string_to_number("0x10"), 16.0);
+    assert_eq!(string_to_number("0XfF"), 255.0);
+    assert_eq!(string_to_number("0o17"), 15.0);
+    assert_eq!(string_to_number("0b101"), 5.0);
+    assert!(string_to_number("0x").is_nan());
+    assert!(string_to_number("-0x10").is_nan());
+    assert!(string_to_number("0b102").is_nan());
+}
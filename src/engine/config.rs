@@ -0,0 +1,39 @@
+use std::rc::Rc;
+
+use super::diagnostics::DiagnosticSink;
+
+#[derive(Debug, Clone)]
+/// Options controlling how the engine lexes, parses, and runs programs.
+pub struct Config {
+    /// Whether to enable the legacy web-compatibility behaviours from Annex B of the spec.  
+    /// This includes legacy octal escapes in strings (`"\101"`) and the `\8` and `\9` escapes.
+    pub annex_b: bool,
+    /// Where to send warnings. If `None`, warnings are discarded.
+    pub diagnostics: Option<Rc<dyn DiagnosticSink>>,
+    /// The maximum number of bytes in a single token, e.g. a string literal or identifier.  
+    /// Longer tokens cause a lexing error rather than being copied. If `None`, there is no limit.
+    pub max_token_length: Option<usize>,
+    /// Whether to treat the whole program as strict mode code.  
+    /// This rejects legacy octal literals (`012`) and escapes (`"\07"`), and reserves words such as `implements`.
+    pub strict: bool,
+    /// Whether to allow the HTML-like comments from Annex B of the spec, when `annex_b` is set and outside of strict mode.  
+    /// `<!--` starts a single line comment, as does `-->` at the start of a line.
+    /// These are only valid in scripts, not modules.
+    pub html_comments: bool,
+    /// Whether to keep comments when lexing, for tools such as formatters.
+    /// If set, comments are available from `Engine::comments`.
+    pub collect_comments: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            annex_b: true,
+            diagnostics: None,
+            max_token_length: None,
+            strict: false,
+            html_comments: false,
+            collect_comments: false,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt::{Debug, Display};
+
+use crate::util::is_line_terminator;
+
+#[derive(Debug, Clone, Copy)]
+/// All the types of non-fatal diagnostics the engine can produce
+pub enum WarningType {
+    /// When an octal literal is written with a leading '0' instead of '0o', e.g. `012`
+    ImplicitOctal,
+}
+
+/// Impl of `Display` for `WarningType`
+impl Display for WarningType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ImplicitOctal => f.write_str("'0'-prefixed octal literals are deprecated; use the '0o' prefix instead"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A non-fatal diagnostic.
+/// Contains the line info and `WarningType`
+pub struct Warning {
+    pub line: usize,
+    pub char: usize,
+    pub index: usize,
+    pub warning_type: WarningType,
+}
+
+impl Warning {
+    #[inline]
+    pub const fn new(line: usize, char: usize, index: usize, w: WarningType) -> Warning {
+        Warning { line, char, index, warning_type: w }
+    }
+}
+
+/// Impl of `Display` for `Warning`
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}:{}: {}", self.line, self.char, self.warning_type))
+    }
+}
+
+/// A receiver for warnings, set on `Config::diagnostics`.  
+/// Warnings never stop a program from running, unlike errors.
+pub trait DiagnosticSink: Debug {
+    fn warn(&self, warning: Warning);
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A `DiagnosticSink` which prints every warning to stderr
+pub struct StderrSink;
+
+impl DiagnosticSink for StderrSink {
+    fn warn(&self, warning: Warning) {
+        eprintln!("Warning: {warning}");
+    }
+}
+
+/// Renders a location in a program as an excerpt of the source, with a caret under the given char.  
+/// `line` and `char` are both 1-based. For example:
+/// ```text
+///  --> test.js:1:9
+///   |
+/// 1 | let a = "abc
+///   |         ^
+/// ```
+pub fn render_excerpt(source: &str, source_name: &str, line: usize, char: usize) -> String {
+    // Lines are split the same way the lexer counts them, with '\r\n' as one line terminator,
+    // and a leading byte order mark not counted as a column
+    let source = source.strip_prefix('\u{FEFF}').unwrap_or(source).replace("\r\n", "\n");
+    let source_line = source.split(is_line_terminator).nth(line - 1).unwrap_or_default();
+    let gutter = " ".repeat(line.to_string().len());
+
+    // Keep tabs before the caret so that it lines up
+    let padding: String = source_line.chars().take(char - 1).map(|c| if c == '\t' {'\t'} else {' '}).collect();
+
+    format!("{gutter}--> {source_name}:{line}:{char}\n{gutter} |\n{line} | {source_line}\n{gutter} | {padding}^")
+}
+
+#[test]
+/// Tests that excerpts show the right line and put the caret under the right char
+fn test_render_excerpt() {
+    let source = "let a = 1;\n\tlet b = \"abc\n";
+    assert_eq!(render_excerpt(source, "test.js", 2, 10), " --> test.js:2:10\n  |\n2 | \tlet b = \"abc\n  | \t        ^");
+    assert_eq!(render_excerpt(source, "test.js", 1, 1), " --> test.js:1:1\n  |\n1 | let a = 1;\n  | ^");
+    assert_eq!(render_excerpt("a\r\nb\rc", "test.js", 3, 1), " --> test.js:3:1\n  |\n3 | c\n  | ^");
+    assert_eq!(render_excerpt("\u{FEFF}ab", "test.js", 1, 2), " --> test.js:1:2\n  |\n1 | ab\n  |  ^");
+}
@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+use crate::lexer::{LexError, LexErrorType};
+
+#[derive(Debug, Clone, Copy)]
+/// Any error returned from the engine's public API.  
+/// Runtime errors and termination will be added here as the engine gains them,
+/// so that embedders can handle every failure in one place.
+pub enum EngineError {
+    /// The program is not valid syntax
+    Syntax(LexError),
+    /// The program went over a limit set on `Config`, such as `max_token_length`
+    ResourceLimit(LexError),
+}
+
+impl EngineError {
+    /// Gets the error's code, which can be looked up with `Explanations`
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::Syntax(e) | Self::ResourceLimit(e) => e.error_type.code(),
+        }
+    }
+
+    /// Gets the line and column in the program where the error occurred, if it has one.
+    /// Both are 1-based, with the column counted in chars.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::Syntax(e) | Self::ResourceLimit(e) => Some((e.line, e.char)),
+        }
+    }
+}
+
+/// Impl of `Display` for `EngineError`
+impl Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syntax(e) => Display::fmt(e, f),
+            // Firefox reports its own limits, such as too much recursion, as an `InternalError`
+            Self::ResourceLimit(e) => f.write_fmt(format_args!("InternalError: {}", e.error_type)),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Syntax(e) | Self::ResourceLimit(e) => Some(e),
+        }
+    }
+}
+
+impl From<LexError> for EngineError {
+    fn from(e: LexError) -> Self {
+        match e.error_type {
+            LexErrorType::TokenTooLong(_) => Self::ResourceLimit(e),
+            _ => Self::Syntax(e),
+        }
+    }
+}
+
+#[test]
+/// Tests that syntax errors keep their message and location
+fn test_syntax_error() {
+    let mut engine = super::Engine::new();
+    let e = engine.parse("let a = 1;\nlet b = 'abc").unwrap_err();
+    assert!(matches!(e, EngineError::Syntax(_)));
+    assert_eq!(e.location(), Some((2, 13)));
+    assert_eq!(e.to_string(), "SyntaxError: '' literal not terminated before end of script");
+    assert!(std::error::Error::source(&e).is_some());
+}
+
+#[test]
+/// Tests that going over `Config::max_token_length` is a resource limit rather than a syntax error
+fn test_resource_limit() {
+    let mut engine = super::Engine::with_config(super::Config { max_token_length: Some(4), ..Default::default() });
+    let e = engine.tokenize("a = 'abcdef'").unwrap_err();
+    assert!(matches!(e, EngineError::ResourceLimit(_)));
+    assert_eq!(e.location(), Some((1, 5)));
+    assert_eq!(e.to_string(), "InternalError: token is longer than the maximum length of 4 bytes");
+}
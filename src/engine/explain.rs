@@ -0,0 +1,87 @@
+//! Extended explanations of errors, looked up by their code.
+
+use std::collections::HashMap;
+
+use crate::lexer::LexErrorType;
+
+/// Declares the code and explanation of each of the engine's errors,
+/// generating both `LexErrorType::code` and the built-in explanations, so that no error can be missing an explanation.  
+/// Codes are never reused, so new errors must be given new codes.
+macro_rules! error_codes {
+    ($($error:pat => $code:literal, $explanation:literal,)*) => {
+        impl LexErrorType {
+            /// Gets the error's code, which can be looked up with `Explanations` or `js --explain`
+            pub const fn code(&self) -> &'static str {
+                match self {
+                    $($error => $code,)*
+                }
+            }
+        }
+
+        /// The explanations of every error the engine can produce
+        const BUILT_IN: &[(&str, &str)] = &[$(($code, $explanation),)*];
+    };
+}
+
+error_codes! {
+    LexErrorType::UnclosedString(_) => "E0001", "A string or template literal was not closed before the end of the program.\nAdd the matching closing quote or backtick.",
+    LexErrorType::NewlineInString(_) => "E0002", "A string literal contains a line break.\nStrings in quotes can't span lines. Use `\\n` to include a line break, or use a template literal.",
+    LexErrorType::IdentifierAfterNumber => "E0003", "A number literal is immediately followed by an identifier, such as in `3in`.\nAdd a space or operator between them.",
+    LexErrorType::MissingDigits(_) => "E0004", "A number prefix such as `0x`, `0o`, or `0b` is not followed by any digits.",
+    LexErrorType::InvalidChar(_) => "E0005", "The program contains a character that can't appear outside of strings and comments.",
+    LexErrorType::OctalEscape => "E0006", "A legacy octal escape such as `\\01` was used in strict mode code or in a template literal.\nUse a hexadecimal escape such as `\\x01` instead.",
+    LexErrorType::NonOctalDecimalEscape => "E0007", "The escapes `\\8` and `\\9` were used in strict mode code or in a template literal.\nWrite the digit without the backslash instead.",
+    LexErrorType::UnclosedRegExp => "E0008", "A regular expression literal was not closed before the end of the line.",
+    LexErrorType::MalformedHexEscape => "E0009", "A `\\x` escape must be followed by exactly two hexadecimal digits, such as `\\x41`.",
+    LexErrorType::MalformedUnicodeEscape => "E0010", "A `\\u` escape must be followed by four hexadecimal digits, such as `\\u0041`, or by hexadecimal digits in braces, such as `\\u{41}`.",
+    LexErrorType::UnicodeEscapeOutOfRange => "E0011", "A `\\u{...}` escape is greater than 0x10FFFF, the largest Unicode code point.",
+    LexErrorType::TokenTooLong(_) => "E0012", "A token is longer than the engine's configured maximum length.\nEmbedders can raise the limit with `Config::max_token_length`.",
+    LexErrorType::LegacyOctalLiteral => "E0013", "A number with a leading `0`, such as `012`, was used in strict mode code.\nUse the `0o` prefix for octal numbers, or remove the leading zero.",
+    LexErrorType::InvalidNumericSeparator => "E0014", "A numeric separator `_` is not between two digits, such as in `1_`, `1__000`, `0x_1`, or `1_.5`.\nSeparators also can't be used in numbers with a leading `0`, such as `0_1` or `01_2`.",
+}
+
+#[derive(Debug, Clone)]
+/// A registry of extended explanations of errors, keyed by error code.  
+/// The default registry contains the engine's own errors,
+/// and embedders can register explanations for their host-specific errors.
+pub struct Explanations {
+    explanations: HashMap<String, String>,
+}
+
+impl Default for Explanations {
+    fn default() -> Self {
+        Explanations {
+            explanations: BUILT_IN.iter().map(|(c, e)| (c.to_string(), e.to_string())).collect(),
+        }
+    }
+}
+
+impl Explanations {
+    /// Adds an explanation for a code, replacing any existing one
+    pub fn register(&mut self, code: &str, explanation: &str) {
+        self.explanations.insert(code.to_string(), explanation.to_string());
+    }
+
+    /// Gets the explanation for a code, if there is one
+    pub fn get(&self, code: &str) -> Option<&str> {
+        self.explanations.get(code).map(String::as_str)
+    }
+}
+
+#[test]
+/// Tests that error codes are unique and can be looked up, and that embedders can add their own
+fn test_explanations() {
+    let mut explanations = Explanations::default();
+    let e = crate::tokenize("0x").unwrap_err();
+    assert_eq!(e.error_type.code(), "E0004");
+    assert!(explanations.get(e.error_type.code()).is_some_and(|e| e.contains("`0x`")));
+    // Codes are in order, so a reused code would be out of place
+    for (i, (code, _)) in BUILT_IN.iter().enumerate() {
+        assert_eq!(*code, format!("E{:04}", i + 1));
+        assert!(explanations.get(code).is_some());
+    }
+
+    assert!(explanations.get("H0001").is_none());
+    explanations.register("H0001", "A host error");
+    assert_eq!(explanations.get("H0001"), Some("A host error"));
+}
@@ -0,0 +1,78 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What kind of thing a `Feature` is
+pub enum FeatureKind {
+    /// A piece of syntax, such as template literals
+    Syntax,
+    /// A built-in object or function, such as `Array.prototype.at`
+    BuiltIn,
+}
+
+/// Impl of `Display` for `FeatureKind`
+impl Display for FeatureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Syntax => "syntax",
+            Self::BuiltIn => "built-in",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A language feature, and whether the engine supports it
+pub struct Feature {
+    /// The name of the feature, in kebab case
+    pub name: &'static str,
+    pub kind: FeatureKind,
+    pub supported: bool,
+}
+
+/// Impl of `Display` for `Feature`.
+/// This is one tab-separated line, so that the report is easy to parse.
+impl Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}\t{}", self.kind, self.name, if self.supported {"yes"} else {"no"})
+    }
+}
+
+/// Declares the feature registry.
+/// Each entry is `kind name => supported`, and features should be listed here as they are implemented or planned.
+macro_rules! features {
+    ($($kind:ident $name:literal => $supported:literal,)*) => {
+        /// Every feature the engine knows about, supported or not
+        pub const FEATURES: &[Feature] = &[$(Feature { name: $name, kind: FeatureKind::$kind, supported: $supported },)*];
+    };
+}
+
+features! {
+    Syntax "hashbang" => true,
+    Syntax "template-literals" => true,
+    Syntax "regexp-literals" => true,
+    Syntax "bigint-literals" => true,
+    Syntax "binary-and-octal-literals" => true,
+    Syntax "numeric-separators" => true,
+    Syntax "numeric-exponents" => false,
+    Syntax "unicode-code-point-escapes" => true,
+    Syntax "private-identifiers" => true,
+    Syntax "exponentiation-operator" => true,
+    Syntax "logical-assignment" => true,
+    Syntax "nullish-coalescing" => true,
+    Syntax "optional-chaining" => true,
+    Syntax "html-like-comments" => true,
+    Syntax "strict-mode" => false,
+    Syntax "statements-and-expressions" => false,
+    BuiltIn "object" => false,
+    BuiltIn "array" => false,
+    BuiltIn "string" => false,
+    BuiltIn "json" => false,
+}
+
+#[test]
+/// Tests that feature names are unique and in kebab case
+fn test_feature_names() {
+    for (i, feature) in FEATURES.iter().enumerate() {
+        assert!(feature.name.chars().all(|c| c.is_ascii_lowercase() || c == '-'), "Bad feature name '{}'", feature.name);
+        assert!(FEATURES[..i].iter().all(|f| f.name != feature.name), "Duplicate feature '{}'", feature.name);
+    }
+}
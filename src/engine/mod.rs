@@ -1,6 +1,18 @@
-use super::Lexer;
+pub mod abstract_ops;
+pub mod config;
+pub mod diagnostics;
+pub mod error;
+pub mod explain;
+pub mod features;
+pub mod number_format;
 
-#[derive(Debug)]
+pub use config::Config;
+pub use error::EngineError;
+
+use super::lexer::{Comment, Lexer, PublicToken};
+use super::lexer::public::to_public;
+
+#[derive(Debug, Default)]
 pub struct Engine {
     lexer: Lexer,
     //parser: super::Parser,
@@ -9,23 +21,34 @@ pub struct Engine {
 
 impl Engine {
     pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
         Engine {
-            lexer: Default::default(),
+            lexer: Lexer::new(config),
         }
     }
 
-    pub fn parse(&mut self, s: &str) {
-        let tokens = self.lexer.lex(s);
-        
-        let Ok(tokens) = tokens else {
-            println!("Error: {tokens:?}");
-            return;
-        };
+    /// Lexes a program without parsing it.  
+    /// Unlike `js::tokenize`, this uses the engine's config.
+    pub fn tokenize(&mut self, s: &str) -> Result<Vec<PublicToken>, EngineError> {
+        let tokens = self.lexer.lex(s)?;
+        Ok(to_public(&tokens, s))
+    }
+
+    /// Gets the comments from the last call to `tokenize`, if `Config::collect_comments` is set
+    pub fn comments(&self) -> &[Comment] {
+        self.lexer.comments()
+    }
 
-        for token in tokens {
-            println!("{token:?}")
+    pub fn parse(&mut self, s: &str) -> Result<(), EngineError> {
+        // Tokens are lexed as they are needed, rather than all up front
+        for token in self.lexer.stream(s) {
+            let _token = token?;
         }
 
         // TODO: parse to AST
+        Ok(())
     }
 }
\ No newline at end of file
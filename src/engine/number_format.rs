@@ -0,0 +1,259 @@
+//! Conversions from numbers to strings, as used by `Number.prototype.toString` and its formatting methods.
+//! Digits are rounded using the exact value of the number, so these match other engines
+//! even where Rust's formatting would round differently, such as `(2.5).toFixed(0)` being `"3"`.
+
+use std::fmt::Display;
+
+use num::BigInt;
+
+#[derive(Debug, Clone, Copy)]
+/// The error when the digits argument to `toFixed`, `toExponential`, or `toPrecision` is out of range.
+/// This is a `RangeError` in JS.
+pub struct PrecisionOutOfRange(pub f64);
+
+/// Impl of `Display` for `PrecisionOutOfRange`
+impl Display for PrecisionOutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("RangeError: precision {} out of range", number_to_string(self.0)))
+    }
+}
+
+/// Gets a finite non-negative number as an exact fraction `(numerator, denominator)`
+fn exact_fraction(x: f64) -> (BigInt, BigInt) {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7FF) as i64;
+    let fraction = bits & 0xF_FFFF_FFFF_FFFF;
+
+    // Subnormal numbers have no implicit leading 1
+    let (mantissa, exponent) = if exponent == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | 1 << 52, exponent - 1075)
+    };
+
+    if exponent >= 0 {
+        (BigInt::from(mantissa) << exponent, BigInt::from(1))
+    } else {
+        (BigInt::from(mantissa), BigInt::from(1) << -exponent)
+    }
+}
+
+/// Rounds a finite non-negative number times `10^scale` to an integer.
+/// Ties are rounded up, as the spec picks the larger `n` when two are equally close.
+fn scale_and_round(x: f64, scale: i64) -> BigInt {
+    let (mut numerator, mut denominator) = exact_fraction(x);
+    let power = BigInt::from(10).pow(scale.unsigned_abs() as u32);
+    if scale >= 0 {
+        numerator *= power;
+    } else {
+        denominator *= power;
+    }
+
+    (numerator * 2 + &denominator) / (denominator * 2)
+}
+
+/// Gets the shortest digits which uniquely identify a finite positive number, and the exponent of the first digit.
+/// For example, `1234.5` gives `("12345", 3)`.
+fn shortest_digits(x: f64) -> (String, i64) {
+    // Rust's exponential formatting uses the shortest digits that round-trip, which is what the spec asks for
+    let formatted = format!("{x:e}");
+    let (mantissa, exponent) = formatted.split_once('e').expect("Should have been exponential notation");
+    (mantissa.replace('.', ""), exponent.parse().expect("Should have been a valid exponent"))
+}
+
+/// Gets a finite positive number rounded to `precision` significant digits, and the exponent of the first digit.
+fn rounded_digits(x: f64, precision: usize) -> (String, i64) {
+    // Start from the exponent of the shortest digits, which can be off by one if rounding carries over
+    let (_, mut exponent) = shortest_digits(x);
+    loop {
+        let digits = scale_and_round(x, precision as i64 - 1 - exponent).to_string();
+        match digits.len().cmp(&precision) {
+            std::cmp::Ordering::Equal => return (digits, exponent),
+            std::cmp::Ordering::Greater => exponent += 1,
+            std::cmp::Ordering::Less => exponent -= 1,
+        }
+    }
+}
+
+/// Formats digits in exponential notation e.g. `"1.2345e+3"`
+fn exponential(digits: &str, exponent: i64) -> String {
+    let sign = if exponent < 0 {'-'} else {'+'};
+    match digits.split_at(1) {
+        (first, "") => format!("{first}e{sign}{}", exponent.abs()),
+        (first, rest) => format!("{first}.{rest}e{sign}{}", exponent.abs()),
+    }
+}
+
+/// The spec's `Number::toString` abstract operation, with a radix of 10.
+/// Converts a number to the shortest string which parses back to the same number.
+pub fn number_to_string(x: f64) -> String {
+    if x.is_nan() {
+        return "NaN".to_string();
+    }
+    // Both 0 and -0 are "0"
+    if x == 0.0 {
+        return "0".to_string();
+    }
+    if x < 0.0 {
+        return format!("-{}", number_to_string(-x));
+    }
+    if x.is_infinite() {
+        return "Infinity".to_string();
+    }
+
+    let (digits, exponent) = shortest_digits(x);
+    let k = digits.len() as i64;
+    // The position of the decimal point relative to the start of the digits
+    let n = exponent + 1;
+
+    if k <= n && n <= 21 {
+        digits + &"0".repeat((n - k) as usize)
+    }
+    else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+        format!("{integer}.{fraction}")
+    }
+    else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat(-n as usize))
+    }
+    else {
+        exponential(&digits, exponent)
+    }
+}
+
+/// `Number.prototype.toFixed`.
+/// `fraction_digits` is the argument after `ToIntegerOrInfinity`, so is 0 if it was `undefined`.
+pub fn to_fixed(x: f64, fraction_digits: f64) -> Result<String, PrecisionOutOfRange> {
+    if !(0.0..=100.0).contains(&fraction_digits) {
+        return Err(PrecisionOutOfRange(fraction_digits));
+    }
+    if !x.is_finite() {
+        return Ok(number_to_string(x));
+    }
+
+    let f = fraction_digits as usize;
+    let (sign, x) = if x < 0.0 {("-", -x)} else {("", x)};
+    // Large numbers aren't written out in full
+    if x >= 1e21 {
+        return Ok(format!("{sign}{}", number_to_string(x)));
+    }
+
+    let mut digits = scale_and_round(x, f as i64).to_string();
+    // Make sure there is at least one digit before the decimal point
+    if digits.len() <= f {
+        digits = "0".repeat(f + 1 - digits.len()) + &digits;
+    }
+
+    if f == 0 {
+        return Ok(format!("{sign}{digits}"));
+    }
+    let (integer, fraction) = digits.split_at(digits.len() - f);
+    Ok(format!("{sign}{integer}.{fraction}"))
+}
+
+/// `Number.prototype.toExponential`.
+/// `fraction_digits` is the argument after `ToIntegerOrInfinity`, or `None` if it was `undefined`,
+/// in which case as many digits as needed are used.
+pub fn to_exponential(x: f64, fraction_digits: Option<f64>) -> Result<String, PrecisionOutOfRange> {
+    if !x.is_finite() {
+        return Ok(number_to_string(x));
+    }
+    let f = fraction_digits.unwrap_or(0.0);
+    if !(0.0..=100.0).contains(&f) {
+        return Err(PrecisionOutOfRange(f));
+    }
+
+    let f = f as usize;
+    let (sign, x) = if x < 0.0 {("-", -x)} else {("", x)};
+    let (digits, exponent) = match fraction_digits {
+        _ if x == 0.0 => ("0".repeat(f + 1), 0),
+        None => shortest_digits(x),
+        Some(_) => rounded_digits(x, f + 1),
+    };
+
+    Ok(format!("{sign}{}", exponential(&digits, exponent)))
+}
+
+/// `Number.prototype.toPrecision`.
+/// `precision` is the argument after `ToIntegerOrInfinity`, or `None` if it was `undefined`,
+/// in which case this is the same as `number_to_string`.
+pub fn to_precision(x: f64, precision: Option<f64>) -> Result<String, PrecisionOutOfRange> {
+    let Some(p) = precision else {
+        return Ok(number_to_string(x));
+    };
+    if !x.is_finite() {
+        return Ok(number_to_string(x));
+    }
+    if !(1.0..=100.0).contains(&p) {
+        return Err(PrecisionOutOfRange(p));
+    }
+
+    let p = p as usize;
+    let (sign, x) = if x < 0.0 {("-", -x)} else {("", x)};
+    let (digits, exponent) = if x == 0.0 {
+        ("0".repeat(p), 0)
+    } else {
+        rounded_digits(x, p)
+    };
+
+    // Very large or small numbers use exponential notation
+    if exponent < -6 || exponent >= p as i64 {
+        return Ok(format!("{sign}{}", exponential(&digits, exponent)));
+    }
+
+    let formatted = if exponent == p as i64 - 1 {
+        digits
+    }
+    else if exponent >= 0 {
+        let (integer, fraction) = digits.split_at(exponent as usize + 1);
+        format!("{integer}.{fraction}")
+    }
+    else {
+        format!("0.{}{digits}", "0".repeat((-exponent - 1) as usize))
+    };
+    Ok(format!("{sign}{formatted}"))
+}
+
+#[test]
+/// Tests `number_to_string` against the results from other engines
+fn test_number_to_string() {
+    let cases = [
+        (0.0, "0"), (-0.0, "0"), (1.5, "1.5"), (-42.0, "-42"), (0.1 + 0.2, "0.30000000000000004"),
+        (1e21, "1e+21"), (123456789012345680000.0, "123456789012345680000"), (0.000001, "0.000001"),
+        (1e-7, "1e-7"), (1.23e-18, "1.23e-18"), (f64::NAN, "NaN"), (f64::NEG_INFINITY, "-Infinity"), (5e-324, "5e-324"),
+    ];
+    for (x, expected) in cases {
+        assert_eq!(number_to_string(x), expected, "for {x:?}");
+    }
+}
+
+#[test]
+/// Tests `to_fixed`, `to_exponential`, and `to_precision`, including rounding of ties and out of range arguments
+fn test_number_formatting() {
+    assert_eq!(to_fixed(2.5, 0.0).unwrap(), "3");
+    assert_eq!(to_fixed(-0.5, 0.0).unwrap(), "-1");
+    assert_eq!(to_fixed(1.005, 2.0).unwrap(), "1.00");
+    assert_eq!(to_fixed(0.000001, 3.0).unwrap(), "0.000");
+    assert_eq!(to_fixed(-0.0000001, 2.0).unwrap(), "-0.00");
+    assert_eq!(to_fixed(1e21, 2.0).unwrap(), "1e+21");
+    assert_eq!(to_fixed(1000000000000000128.0, 0.0).unwrap(), "1000000000000000128");
+
+    assert_eq!(to_exponential(123.456, Some(2.0)).unwrap(), "1.23e+2");
+    assert_eq!(to_exponential(25.0, Some(0.0)).unwrap(), "3e+1");
+    assert_eq!(to_exponential(9.99, Some(1.0)).unwrap(), "1.0e+1");
+    assert_eq!(to_exponential(0.00015, None).unwrap(), "1.5e-4");
+    assert_eq!(to_exponential(0.0, None).unwrap(), "0e+0");
+    assert_eq!(to_exponential(-0.0, Some(2.0)).unwrap(), "0.00e+0");
+
+    assert_eq!(to_precision(123.456, Some(4.0)).unwrap(), "123.5");
+    assert_eq!(to_precision(0.00001, Some(1.0)).unwrap(), "0.00001");
+    assert_eq!(to_precision(1e-7, Some(1.0)).unwrap(), "1e-7");
+    assert_eq!(to_precision(123456.0, Some(2.0)).unwrap(), "1.2e+5");
+    assert_eq!(to_precision(0.0, Some(3.0)).unwrap(), "0.00");
+    assert_eq!(to_precision(1.5, None).unwrap(), "1.5");
+
+    assert!(to_fixed(1.0, 101.0).is_err());
+    assert!(to_fixed(f64::NAN, f64::INFINITY).is_err());
+    assert_eq!(to_exponential(f64::INFINITY, Some(-1.0)).unwrap(), "Infinity");
+    assert_eq!(to_precision(1.0, Some(0.0)).unwrap_err().to_string(), "RangeError: precision 0 out of range");
+}
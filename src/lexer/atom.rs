@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::rc::Rc;
+
+#[derive(Clone)]
+/// An interned string, used for identifiers.
+/// Atoms from the same `Interner` with the same text share one allocation, so they are compared and hashed by pointer.
+/// Comparing atoms from different interners is not meaningful.
+pub struct Atom(Rc<str>);
+
+impl Deref for Atom {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Atom {}
+
+impl Hash for Atom {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::ptr::hash(Rc::as_ptr(&self.0) as *const u8, state)
+    }
+}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+/// Impl of `Debug` for `Atom`.
+/// This is the same as for a string, so that tokens print the same as before interning.
+impl Debug for Atom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&*self.0, f)
+    }
+}
+
+#[derive(Debug, Default)]
+/// Stores one copy of each identifier, so that each is only allocated once.
+/// The interner lives as long as the `Lexer`, so atoms are shared between programs lexed by the same engine.
+/// Atoms which are no longer used by anything else are removed by `prune`, so the interner doesn't grow forever.
+pub(crate) struct Interner {
+    atoms: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    /// Gets the atom for a string, allocating it if it hasn't been seen before
+    pub fn intern(&mut self, s: &str) -> Atom {
+        if let Some(atom) = self.atoms.get(s) {
+            return Atom(atom.clone());
+        }
+
+        let atom: Rc<str> = Rc::from(s);
+        self.atoms.insert(atom.clone());
+        Atom(atom)
+    }
+
+    /// Removes every atom whose only reference is the interner's own
+    pub fn prune(&mut self) {
+        self.atoms.retain(|atom| Rc::strong_count(atom) > 1);
+    }
+}
+
+#[test]
+/// Tests that interning the same string twice gives the same atom
+fn test_interning() {
+    let mut interner = Interner::default();
+    let a = interner.intern("abc");
+    let b = interner.intern(&String::from("abc"));
+    let c = interner.intern("abd");
+
+    assert_eq!(a, b);
+    assert!(Rc::ptr_eq(&a.0, &b.0));
+    assert_ne!(a, c);
+    assert!(a == *"abc" && &*a == "abc");
+    assert_eq!(format!("{a:?}"), "\"abc\"");
+}
+
+#[test]
+/// Tests that pruning only removes atoms which aren't used outside the interner
+fn test_prune() {
+    let mut interner = Interner::default();
+    let a = interner.intern("abc");
+    interner.intern("abd");
+    interner.prune();
+
+    assert_eq!(interner.atoms.len(), 1);
+    assert_eq!(interner.intern("abc"), a);
+    drop(a);
+    interner.prune();
+    assert!(interner.atoms.is_empty());
+}
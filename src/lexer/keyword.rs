@@ -0,0 +1,137 @@
+/// Declares the `Keyword` enum, generating `Keyword::from_name` and `Keyword::as_str` from the same table.
+macro_rules! keywords {
+    (
+        reserved: {$($reserved_name:literal => $reserved:ident,)*}
+        contextual: {$($contextual_name:literal => $contextual:ident,)*}
+    ) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        /// A keyword.  
+        /// Reserved words are lexed as `TokenType::Keyword`.
+        /// Contextual keywords can also be identifiers (e.g. `let of = 1`), so they are lexed as `TokenType::Identifier`,
+        /// and the parser can check for them with `TokenType::is_keyword`.
+        pub enum Keyword {
+            $(
+                #[doc = concat!("`", $reserved_name, "`")]
+                $reserved,
+            )*
+            $(
+                #[doc = concat!("`", $contextual_name, "` (contextual)")]
+                $contextual,
+            )*
+        }
+
+        impl Keyword {
+            /// Gets the keyword with the given name, if there is one
+            pub fn from_name(name: &str) -> Option<Keyword> {
+                match name {
+                    $($reserved_name => Some(Keyword::$reserved),)*
+                    $($contextual_name => Some(Keyword::$contextual),)*
+                    _ => None,
+                }
+            }
+
+            /// Gets the keyword's source text
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(Keyword::$reserved => $reserved_name,)*
+                    $(Keyword::$contextual => $contextual_name,)*
+                }
+            }
+
+            /// Whether the keyword is contextual, meaning it can also be used as an identifier
+            pub fn is_contextual(&self) -> bool {
+                match self {
+                    $(Keyword::$contextual => true,)*
+                    _ => false,
+                }
+            }
+        }
+    };
+}
+
+keywords! {
+    reserved: {
+        "break" => Break,
+        "case" => Case,
+        "catch" => Catch,
+        "class" => Class,
+        "const" => Const,
+        "continue" => Continue,
+        "debugger" => Debugger,
+        "default" => Default,
+        "delete" => Delete,
+        "do" => Do,
+        "else" => Else,
+        "enum" => Enum,
+        "export" => Export,
+        "extends" => Extends,
+        "false" => False,
+        "finally" => Finally,
+        "for" => For,
+        "function" => Function,
+        "if" => If,
+        "import" => Import,
+        "in" => In,
+        "instanceof" => Instanceof,
+        "new" => New,
+        "null" => Null,
+        "return" => Return,
+        "super" => Super,
+        "switch" => Switch,
+        "this" => This,
+        "throw" => Throw,
+        "true" => True,
+        "try" => Try,
+        "typeof" => Typeof,
+        "var" => Var,
+        "void" => Void,
+        "while" => While,
+        "with" => With,
+    }
+    contextual: {
+        "as" => As,
+        "async" => Async,
+        "await" => Await,
+        "from" => From,
+        "get" => Get,
+        "let" => Let,
+        "meta" => Meta,
+        "of" => Of,
+        "set" => Set,
+        "static" => Static,
+        "target" => Target,
+        "yield" => Yield,
+
+        // Only reserved in strict mode code
+        "implements" => Implements,
+        "interface" => Interface,
+        "package" => Package,
+        "private" => Private,
+        "protected" => Protected,
+        "public" => Public,
+    }
+}
+
+impl Keyword {
+    /// Whether the keyword is one of the words only reserved in strict mode code, such as `implements` or `let`.  
+    /// These are lexed as `TokenType::Keyword` when `Config::strict` is set.
+    pub fn is_strict_reserved(&self) -> bool {
+        matches!(self,
+            Keyword::Implements | Keyword::Interface | Keyword::Let | Keyword::Package | Keyword::Private
+            | Keyword::Protected | Keyword::Public | Keyword::Static | Keyword::Yield
+        )
+    }
+}
+
+#[test]
+/// Tests that every keyword's name gives back the same keyword
+fn test_keyword_names() {
+    for name in ["if", "typeof", "let", "of", "implements"] {
+        let keyword = Keyword::from_name(name).unwrap();
+        assert_eq!(keyword.as_str(), name);
+    }
+    assert!(!Keyword::from_name("if").unwrap().is_contextual());
+    assert!(Keyword::from_name("let").unwrap().is_contextual());
+    assert_eq!(Keyword::from_name("foo"), None);
+    assert_eq!(Keyword::from_name("If"), None);
+}
@@ -1,12 +1,24 @@
+pub mod atom;
+pub mod keyword;
+pub mod public;
+pub mod span;
 pub mod token;
 
 use std::fmt::Display;
 
-pub(crate) use token::{Token, TokenType};
+pub use atom::Atom;
+pub(crate) use atom::Interner;
+pub use keyword::Keyword;
+pub use span::Span;
+pub use token::{Comment, CommentKind};
+pub use token::{LexGoal, RegExp, Token, TokenType};
+pub use public::{PublicToken, TokenKind};
 
 use num::{BigInt, Num, ToPrimitive};
 
-use crate::util::is_identifier_continue;
+use crate::engine::Config;
+use crate::engine::diagnostics::{Warning, WarningType};
+use crate::util::{is_identifier_continue, is_line_terminator, is_whitespace};
 pub use crate::util::{is_identifier_start, NumberLiteralBase};
 
 use self::token::OPERATORS;
@@ -26,6 +38,26 @@ pub enum LexErrorType {
     MissingDigits(NumberLiteralBase),
     /// When an invalid unicode occurs outside of a string
     InvalidChar(char),
+    /// When a legacy octal escape sequence such as `\01` occurs where it is not allowed
+    OctalEscape,
+    /// When a `\8` or `\9` escape sequence occurs where it is not allowed
+    NonOctalDecimalEscape,
+    /// When an EOF or newline occurs during a regex literal
+    UnclosedRegExp,
+    /// When a `\x` escape sequence is not followed by two hex digits
+    MalformedHexEscape,
+    /// When a `\u` escape sequence is not followed by four hex digits or by hex digits in braces
+    MalformedUnicodeEscape,
+    /// When a `\u{...}` escape sequence is greater than 0x10FFFF
+    UnicodeEscapeOutOfRange,
+    /// When a token is longer than `Config::max_token_length`.
+    /// usize is the maximum length
+    TokenTooLong(usize),
+    /// When a number literal starts with a `0` followed by digits, such as `012` or `089`, in strict mode
+    LegacyOctalLiteral,
+    /// When a `_` in a number literal isn't between two digits, such as `1_`, `1__0`, or `0x_1`,
+    /// or is in a number with a leading `0`
+    InvalidNumericSeparator,
 }
 
 /// Impl of `Display` for `LexErrorType`
@@ -36,7 +68,16 @@ impl Display for LexErrorType {
             Self::NewlineInString(c) => f.write_fmt(format_args!("{c}{c} literal contains an unescaped line break")),
             Self::IdentifierAfterNumber => f.write_str("identifier starts immediately after numeric literal"),
             Self::MissingDigits(n) => f.write_fmt(format_args!("missing {} digits after '{}'", n.get_name(), n.get_start())),
-            Self::InvalidChar(c) => f.write_fmt(format_args!("illegal character U+{:x}", *c as u32))
+            Self::InvalidChar(c) => f.write_fmt(format_args!("illegal character U+{:x}", *c as u32)),
+            Self::OctalEscape => f.write_str("octal escape sequences can't be used in untagged template literals or in strict mode code"),
+            Self::NonOctalDecimalEscape => f.write_str("the escapes \\8 and \\9 can't be used in untagged template literals or in strict mode code"),
+            Self::UnclosedRegExp => f.write_str("unterminated regular expression literal"),
+            Self::MalformedHexEscape => f.write_str("malformed hexadecimal character escape sequence"),
+            Self::MalformedUnicodeEscape => f.write_str("malformed Unicode character escape sequence"),
+            Self::UnicodeEscapeOutOfRange => f.write_str("Unicode codepoint must not be greater than 0x10FFFF in escape sequence"),
+            Self::TokenTooLong(n) => f.write_fmt(format_args!("token is longer than the maximum length of {n} bytes")),
+            Self::LegacyOctalLiteral => f.write_str("numbers with a leading '0' can't be used in strict mode code; use the '0o' prefix for octal"),
+            Self::InvalidNumericSeparator => f.write_str("numeric separators '_' are only allowed between two digits, and not in numbers that start with '0'"),
         }
     }
 }
@@ -46,294 +87,1190 @@ impl Display for LexErrorType {
 /// Contains the line info and `LexErrorType`
 pub struct LexError {
     pub line: usize,
+    /// The column, counted in chars
     pub char: usize,
+    /// The byte index into the program
     pub index: usize,
     pub error_type: LexErrorType,
 }
 
+/// Impl of `Display` for `LexError`
+impl Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("SyntaxError: {}", self.error_type))
+    }
+}
+
+impl std::error::Error for LexError {}
+
 impl LexError {
     #[inline]
-    const fn new(line: usize, line_index: usize, token_start: usize, e: LexErrorType) -> LexError {
-        LexError { line, char: token_start - line_index + 1, index: token_start, error_type: e }
+    const fn new(line: usize, char: usize, index: usize, e: LexErrorType) -> LexError {
+        LexError { line, char, index, error_type: e }
+    }
+}
+
+
+/// Parses a string as a hex number, or returns `None` if any char is not a hex digit
+fn parse_hex(digits: &str) -> Option<u32> {
+    digits.chars().try_fold(0u32, |value, c| Some(value.checked_mul(16)? + c.to_digit(16)?))
+}
+
+/// Reads the part of a unicode escape after the `\u`, from the start of `s`.  
+/// Returns the value of the escape, which may be a surrogate, and the number of bytes it used.
+fn read_unicode_escape(s: &str) -> Result<(u32, usize), LexErrorType> {
+    // Escape with braces e.g. '\u{1F600}'
+    if let Some(rest) = s.strip_prefix('{') {
+        let digits = rest.bytes().take_while(u8::is_ascii_hexdigit).count();
+        if digits == 0 || rest.as_bytes().get(digits) != Some(&b'}') {
+            return Err(LexErrorType::MalformedUnicodeEscape);
+        }
+        // Leading zeros are allowed, so only the value is limited
+        match parse_hex(&rest[..digits]) {
+            Some(value) if value <= 0x10FFFF => Ok((value, digits + 2)),
+            _ => Err(LexErrorType::UnicodeEscapeOutOfRange),
+        }
+    }
+    // Escape with exactly four digits e.g. 'A'
+    else {
+        match s.get(..4).and_then(parse_hex) {
+            Some(value) => Ok((value, 4)),
+            None => Err(LexErrorType::MalformedUnicodeEscape),
+        }
     }
 }
 
 #[derive(Debug, Default)]
 /// Struct responsible for lexical analysis.
-pub struct Lexer {}
+pub struct Lexer {
+    config: Config,
+    /// The comments from the last call to `lex`, if `Config::collect_comments` is set
+    comments: Vec<Comment>,
+    /// The identifiers from programs lexed so far which are still in use
+    interner: Interner,
+}
 
 impl Lexer {
+    /// Creates a lexer which uses the given config
+    pub fn new(config: Config) -> Self {
+        Lexer { config, comments: vec![], interner: Interner::default() }
+    }
+
+    /// Gets the comments from the last call to `lex`.  
+    /// This is always empty unless `Config::collect_comments` is set.
+    pub(crate) fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
     /// Constructs a list of tokens from a string.
     pub(crate) fn lex(&mut self, s: &str) -> Result<Vec<Token>, LexError> {
-        // Stores the tokens
-        let mut tokens: Vec<Token> = vec![];
-
-        // Convert string to Vec<char> for easier access by index
-        let program: Vec<char> = s.chars().collect();
-
-        // The current index into `program`
-        let mut i = 0;
-        // The current line
-        let mut line = 1;
-        // The index into `program` of the start of the current line
-        let mut line_index = 0;
-
-        // The value of `i` from the start of the previous loop
-        // Used to detect if `i` has not changed since the last loop, to detect infinite loops
-        let mut prev_i = 0;
-
-        'tokens: loop {
-            // Panic if an infinite loop is detected
-            if i == prev_i && i != 0 {
-                panic!("Loop detected: line {line}:{}\ntokens so far:{tokens:?}", i-line_index);
+        self.interner.prune();
+        let mut stream = TokenStream::new(&self.config, &mut self.interner, s);
+        let tokens = stream.by_ref().collect();
+        self.comments = stream.comments;
+
+        tokens
+    }
+
+    /// Creates an iterator which lexes tokens from a string as they are needed.  
+    /// Unlike `lex`, comments are not stored in the lexer, but are available from `TokenStream::comments`.
+    pub fn stream<'a>(&'a mut self, s: &'a str) -> TokenStream<'a> {
+        self.interner.prune();
+        TokenStream::new(&self.config, &mut self.interner, s)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A position in the program, saved at the start of a token
+struct Mark {
+    line: usize,
+    /// The byte index of the start of the line
+    line_index: usize,
+    /// The byte index of the position
+    index: usize,
+}
+
+/// An iterator over the tokens of one program, which lexes each token when it is needed.  
+/// After an error, the iterator ends.
+/// 
+/// Positions are byte indexes into the program, and columns are counted in chars when needed.
+/// The stream tracks the `LexGoal` itself, so tools can use it to get the same tokens the engine would.
+pub struct TokenStream<'a> {
+    config: &'a Config,
+    interner: &'a mut Interner,
+    program: &'a str,
+    /// The current byte index into `program`
+    i: usize,
+    /// The current line
+    line: usize,
+    /// The byte index into `program` of the start of the current line
+    line_index: usize,
+    /// The last byte index a column was computed for, and that column.
+    /// Counting from here rather than the start of the line keeps columns cheap on long lines.
+    column_cache: (usize, usize),
+    /// The number of unclosed '{' in each template substitution currently being lexed, innermost last.
+    /// A '}' when the innermost count is 0 ends the substitution rather than being a `CloseBrace`.
+    template_braces: Vec<usize>,
+    /// For each unclosed '(', the `if`, `while`, `for`, or `with` whose condition it starts, or `None`, innermost last
+    control_parens: Vec<Option<Keyword>>,
+    /// The goal to use for the next token
+    goal: LexGoal,
+    /// Whether the last significant token was a `.`
+    after_dot: bool,
+    /// Whether the last significant token was `for`
+    after_for: bool,
+    /// The `if`, `while`, `for`, or `with` whose condition a '(' now would start, if any
+    control_paren_next: Option<Keyword>,
+    /// The line the last token ended on, or `None` if it was a `NewLine` or there wasn't one
+    last_token_line: Option<usize>,
+    /// The number of tokens lexed so far, not counting `NewLine`s.
+    /// This is the index the next token will have in the list of `PublicToken`s.
+    token_count: usize,
+    /// The token which has been lexed but not yet returned by `next`
+    next_token: Option<Token>,
+    /// Whether the end of the program or an error has been reached
+    finished: bool,
+    comments: Vec<Comment>,
+    /// The indexes into `comments` of leading comments waiting for the next significant token
+    leading_comments: Vec<usize>,
+}
+
+impl<'a> TokenStream<'a> {
+    fn new(config: &'a Config, interner: &'a mut Interner, program: &'a str) -> Self {
+        let mut stream = TokenStream {
+            config,
+            interner,
+            program,
+            i: 0,
+            line: 1,
+            line_index: 0,
+            column_cache: (0, 1),
+            template_braces: vec![],
+            control_parens: vec![],
+            goal: LexGoal::RegExp,
+            after_dot: false,
+            after_for: false,
+            control_paren_next: None,
+            last_token_line: None,
+            token_count: 0,
+            next_token: None,
+            finished: false,
+            comments: vec![],
+            leading_comments: vec![],
+        };
+
+        // Skip a byte order mark at the start of the program, so that a hashbang can follow it.
+        // The first line starts after it, so that it doesn't count as a column.
+        if program.starts_with('\u{FEFF}') {
+            stream.advance();
+            stream.line_index = stream.i;
+            stream.column_cache = (stream.i, 1);
+        }
+
+        // Skip a hashbang comment at the very start of the program e.g. '#!/usr/bin/env js'
+        if program[stream.i..].starts_with("#!") {
+            while stream.peek().is_some_and(|c| !is_line_terminator(c)) {
+                stream.advance();
             }
-            prev_i = i;
+        }
 
-            // Store the current `i` to calculate the token's line and column
-            let token_start = i;
+        stream
+    }
 
-            // Get char or break on EOF
-            let Some(&c) = program.get(i) else {
-                break 'tokens;
+    /// Gets the comments lexed so far.  
+    /// This is always empty unless `Config::collect_comments` is set.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    /// Gets the char at the current index without consuming it
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.program[self.i..].chars().next()
+    }
+
+    /// Gets the char after the one at the current index
+    #[inline]
+    fn peek_next(&self) -> Option<char> {
+        self.program[self.i..].chars().nth(1)
+    }
+
+    /// Consumes the char at the current index
+    #[inline]
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.i += c.len_utf8();
+        Some(c)
+    }
+
+    /// Records that a newline has just been consumed
+    fn new_line(&mut self) {
+        // '\r\n' is a single line terminator
+        if self.program[..self.i].ends_with('\r') && self.peek() == Some('\n') {
+            self.i += 1;
+        }
+        self.line += 1;
+        self.line_index = self.i;
+    }
+
+    /// Gets the current position
+    fn mark(&self) -> Mark {
+        Mark { line: self.line, line_index: self.line_index, index: self.i }
+    }
+
+    /// Gets the column of a position, counted in chars
+    fn column(&mut self, at: Mark) -> usize {
+        let (cache_index, cache_column) = self.column_cache;
+        let column = if (at.line_index..=at.index).contains(&cache_index) {
+            cache_column + self.program[cache_index..at.index].chars().count()
+        } else {
+            1 + self.program[at.line_index..at.index].chars().count()
+        };
+        self.column_cache = (at.index, column);
+        column
+    }
+
+    /// Creates an error at the given position
+    fn error(&mut self, at: Mark, e: LexErrorType) -> LexError {
+        LexError::new(at.line, self.column(at), at.index, e)
+    }
+
+    /// Sets the next token to one which started at the given position
+    fn push_token(&mut self, start: Mark, t: TokenType) {
+        if matches!(t, TokenType::NewLine) {
+            self.last_token_line = None;
+        }
+        else {
+            let index = self.token_count;
+            self.token_count += 1;
+            self.last_token_line = Some(self.line);
+            // Attach leading comments to this token, rather than a newline
+            for comment in std::mem::take(&mut self.leading_comments) {
+                self.comments[comment].token = index;
+            }
+
+            // `of` is only a keyword in the head of a `for`, after the expression being assigned to e.g. `for (a of /b/g)`
+            let for_of = self.goal == LexGoal::Div && self.control_parens.last() == Some(&Some(Keyword::For));
+            self.goal = LexGoal::after(&t, self.after_dot);
+            if for_of && matches!(&t, TokenType::Identifier(i) if i == "of") {
+                self.goal = LexGoal::RegExp;
+            }
+            // Keywords after a `.` are property names e.g. `a.if(x) / 2`, so don't start a control statement
+            self.control_paren_next = match &t {
+                _ if self.after_dot => None,
+                TokenType::Keyword(k @ (Keyword::If | Keyword::While | Keyword::For | Keyword::With)) => Some(*k),
+                // `for await (...)`
+                TokenType::Identifier(i) if i == "await" && self.after_for => Some(Keyword::For),
+                _ => None,
             };
+            self.after_for = matches!(t, TokenType::Keyword(Keyword::For)) && !self.after_dot;
+            self.after_dot = matches!(t, TokenType::OperatorDot);
+        }
+
+        self.next_token = Some(Token::new(start.line, self.column(start), Span::new(start.index, self.i), t));
+    }
+
+    /// Sends a warning to the config's `DiagnosticSink`, if there is one
+    fn warn(&mut self, at: Mark, w: WarningType) {
+        let config = self.config;
+        if let Some(sink) = &config.diagnostics {
+            sink.warn(Warning::new(at.line, self.column(at), at.index, w));
+        }
+    }
+
+    /// Errors if the token starting at `start` has become longer than `Config::max_token_length`
+    fn check_token_length(&mut self, start: Mark) -> Result<(), LexError> {
+        match self.config.max_token_length {
+            Some(max) if self.i - start.index > max => Err(self.error(start, LexErrorType::TokenTooLong(max))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records a comment if `Config::collect_comments` is set.  
+    /// The comment is attached to the previous token if it is on the same line, or to the next token otherwise.
+    fn collect_comment(&mut self, start: Mark, kind: CommentKind, text: &str) {
+        if !self.config.collect_comments {
+            return;
+        }
+
+        let trailing = self.last_token_line == Some(start.line);
+        if !trailing {
+            self.leading_comments.push(self.comments.len());
+        }
+        let comment = Comment {
+            line: start.line,
+            char: self.column(start),
+            span: Span::new(start.index, self.i),
+            kind,
+            text: text.to_string(),
+            // Leading comments are attached to the end of the program, until there is a next token
+            token: if trailing {self.token_count - 1} else {self.token_count},
+            trailing,
+        };
+        self.comments.push(comment);
+    }
+
+    /// Lexes the next token, comment, or whitespace, or marks the stream as finished at the end of the program
+    fn lex_next(&mut self) -> Result<(), LexError> {
+        // Store the current position to calculate the token's line and column
+        let start = self.mark();
+
+        let Some(c) = self.peek() else {
+            self.finished = true;
+            // Error if the program ends inside a template substitution
+            if !self.template_braces.is_empty() {
+                return Err(self.error(start, LexErrorType::UnclosedString('`')));
+            }
+            return Ok(());
+        };
+
+        match c {
+            // String or template literal, or the rest of a template literal after a substitution
+            '"' | '\'' | '`' => self.lex_string_literal(start, c)?,
+            '}' if self.template_braces.last() == Some(&0) => self.lex_string_literal(start, c)?,
+
+            // Number or BigInt literal, including numbers starting with a decimal point e.g. '.5'
+            '0'..='9' => self.lex_number(start)?,
+            '.' if self.peek_next().is_some_and(|c| c.is_ascii_digit()) => self.lex_number(start)?,
+
+            // Newline, including '\r\n' and the unicode line and paragraph separators
+            c if is_line_terminator(c) => {
+                self.advance();
+                self.new_line();
+                self.push_token(start, TokenType::NewLine);
+            }
+
+            // Ignore whitespace, including the non-ASCII spaces and <ZWNBSP>
+            w if is_whitespace(w) => {self.advance();}
+
+            // Comments
+            '/' if self.peek_next() == Some('/') => self.lex_line_comment(start, "//"),
+            '/' if self.peek_next() == Some('*') => self.lex_block_comment(start),
+            // HTML-like comments, which are a single line comment starting with '<!--',
+            // or starting with '-->' if it is the first token on its line
+            '<' if self.html_comments_allowed() && self.program[self.i..].starts_with("<!--") => self.lex_line_comment(start, "<!--"),
+            '-' if self.html_comments_allowed() && self.last_token_line != Some(self.line) && self.program[self.i..].starts_with("-->") => {
+                self.lex_line_comment(start, "-->")
+            }
+
+            // Regex literals, if a '/' can't be division here
+            '/' if self.goal == LexGoal::RegExp => self.lex_regex(start)?,
+
+            // A private identifier e.g. '#field'
+            '#' if self.peek_next().is_some_and(is_identifier_start) => self.lex_identifier(start)?,
+
+            // An identifier
+            c if is_identifier_start(c) => self.lex_identifier(start)?,
+
+            // Any other character: should be an operator
+            c => self.lex_operator(start, c)?,
+        }
+
+        Ok(())
+    }
+
+    /// Lexes a string or template literal starting with `opening`.  
+    /// If `opening` is '}', this is the rest of a template literal after a substitution.
+    fn lex_string_literal(&mut self, start: Mark, opening: char) -> Result<(), LexError> {
+        self.advance();
+        // The char which ends the string. A '}' here is the end of a template substitution
+        let quote = if opening == '}' {'`'} else {opening};
+        // Whether the string ended with '${' rather than the quote
+        let mut substitution_follows = false;
+        let mut s = String::new();
+
+        'string: loop {
+            let char_start = self.mark();
+            let Some(c) = self.advance() else {
+                // Error on EOF
+                return Err(self.error(char_start, LexErrorType::UnclosedString(quote)));
+            };
+            self.check_token_length(start)?;
 
             match c {
-                // String literal
-                quote if quote == '"' || quote == '\'' || quote == '`' => {
-                    let mut s = String::new();
-                    'string: loop {
-                        i += 1;
-                        match program.get(i) {
-                            // Error on EOF
-                            None => return Err(LexError::new(line, line_index, i, LexErrorType::UnclosedString(quote))),
-                            // Error on newlines in the string
-                            // Does not error for backtick enclosed strings
-                            Some('\n') if quote != '`' => return Err(LexError::new(line, line_index, i, LexErrorType::NewlineInString(quote))),
-                            // If in a backtick string, update line on newline
-                            Some('\n') => {
-                                line += 1;
-                                line_index = i;
-                            }
-                            // Detect the end of the string
-                            Some(&c) if c == quote => break 'string,
-                            // Parse escape sequences
-                            Some('\\') => {
-                                i += 1;
-                                // Add character to string
-                                s += &match program.get(i) {
-                                    None => return Err(LexError::new(line, line_index, i, LexErrorType::UnclosedString(quote))),
-                                    // Line continuation
-                                    Some('\n') => "".to_string(),
-                                    // Newline
-                                    Some('n') => "\n".to_string(),
-                                    // Carriage return
-                                    Some('r') => "\r".to_string(),
-                                    // Tab
-                                    Some('t') => "\t".to_string(),
-                                    // Backspace
-                                    Some('b') => "\u{0008}".to_string(),
-                                    // Form feed
-                                    Some('f') => "\u{000C}".to_string(),
-                                    // Vertical tab
-                                    Some('v') => "\u{000C}".to_string(),
-                                    // TODO: unicode strings
-                                    Some('u') | Some('x') => todo!(),
-                                    // Any other character
-                                    Some(c) => c.to_string()
-                                };
-                            }
-                            // If any other char, add it to the string
-                            Some(c) => {
-                                s += &c.to_string();
-                            }
-                        }
+                // Error on newlines in the string
+                // Does not error for backtick enclosed strings
+                '\n' | '\r' if quote != '`' => return Err(self.error(char_start, LexErrorType::NewlineInString(quote))),
+                // If in a backtick string, update line on newline
+                // <LS> and <PS> are allowed in all strings, but still start a new line
+                c if is_line_terminator(c) => {
+                    // Templates normalise '\r\n' and '\r' to '\n'
+                    s.push(if c == '\r' {'\n'} else {c});
+                    self.new_line();
+                }
+                // Detect the end of the string
+                c if c == quote => break 'string,
+                // Detect the start of a template substitution
+                '$' if quote == '`' && self.peek() == Some('{') => {
+                    self.advance();
+                    substitution_follows = true;
+                    break 'string;
+                }
+                // Parse escape sequences
+                '\\' => {
+                    if let Some(c) = self.lex_escape(char_start, quote)? {
+                        s.push(c);
                     }
-                    tokens.push(Token::new(line, line_index, token_start, TokenType::StringLiteral(s)));
-                    i += 1;
                 }
+                // If any other char, add it to the string
+                c => s.push(c),
+            }
+        }
 
-                // Number or BigInt literal
-                digit if '0' <= digit && digit <= '9' => {
-                    // Init base to decimal
-                    let mut base = NumberLiteralBase::Decimal;
-                    // Could be '0' literal, octal string e.g. '012' meaning 10, or start of '0x', '0b', etc.
-                    if digit == '0' {
-                        i += 1;
-                        match program.get(i) {
-                            // If EOF here, generate `NumberLiteral(0)`
-                            None => {
-                                tokens.push(Token::new(line, line_index, token_start, TokenType::NumberLiteral(0.0)));
-                                break 'tokens;
-                            },
-                            Some(&c) => base = match c {
-                                // Hex literal
-                                'x' | 'X' => {i += 1; NumberLiteralBase::Hex},
-                                // Octal literal
-                                'o' | 'O' | '0' => {i += 1; NumberLiteralBase::Octal},
-                                // Binary literal
-                                'b' | 'B' => {i += 1; NumberLiteralBase::Binary},
-                                // Bigint '0n' literal
-                                'n' => {
-                                    // Error if the next char is an identifier
-                                    if let Some(&c) = program.get(i + 1) {
-                                        if is_identifier_start(c) {
-                                            return Err(LexError::new(line, line_index, i, LexErrorType::IdentifierAfterNumber))
-                                        }
-                                    }
-                                    // Generate `BigIntLiteral(0)` token
-                                    tokens.push(Token::new(line, line_index, token_start, TokenType::BigIntLiteral(BigInt::from(0))));
-                                    i += 2;
-                                    continue 'tokens;
-                                },
-                                // TODO: error here in strict mode
-                                // Octal literal with no '0o' or '0O'
-                                c if '1' <= c && c <= '9' => NumberLiteralBase::Octal,
-                                // Error if identifier encountered
-                                c if is_identifier_start(c) => {
-                                    return Err(LexError::new(line, line_index, i, LexErrorType::IdentifierAfterNumber))
-                                }
-                                _ => {
-                                    tokens.push(Token::new(line, line_index, token_start, TokenType::NumberLiteral(0.0)));
-                                    continue 'tokens;
-                                }
-                            }
-                        };
-                    }
-                    
-                    // Start of the digits
-                    let digits_start = i;
-                    // Whether there has been a decimal point yet
-                    let mut had_decimal = false;
-                    // The number for the string
-                    let mut number = String::new();
-
-                    'digits: loop {
-                        match program.get(i) {
-                            // Error on EOF
-                            None => {
-                                if digits_start == i {
-                                    return Err(LexError::new(line, line_index, i, LexErrorType::MissingDigits(base)))
-                                } 
-                                break 'digits;
-                            },
-                            // Indicates a BigInt literal instead of a number
-                            Some('n') => {
-                                if had_decimal {return Err(LexError::new(line, line_index, i, LexErrorType::IdentifierAfterNumber))}
-                                tokens.push(Token::new(line, line_index, token_start, TokenType::BigIntLiteral(BigInt::from_str_radix(&number, base.get_radix()).expect("Should have been a valid bigint"))));
-                                i += 1;
-                                continue 'tokens;
-                            },
-                            // A digit
-                            Some(digit) if base.get_chars().contains(&digit.to_string()) => {number += &digit.to_string()},
-                            // Underscores are ignored in numeric literals
-                            Some('_') => (),
-                            // A newline
-                            Some('\n') => {
-                                // Decrement i so that the next loop iteration finds the newline
-                                i -= 1;
-                                break 'digits;
-                            },
-                            Some('.') if base == NumberLiteralBase::Decimal => {
-                                had_decimal = true;
-                            }
-                            // Error if an identifier is found
-                            Some(&id) if is_identifier_start(id) => {return Err(LexError::new(line, line_index, i, LexErrorType::IdentifierAfterNumber))},
-                            // Any other character means the end of the number
-                            _ => break 'digits,
-                        }
-                        i += 1;
-                    }
-                    i += 1;
-                    if base == NumberLiteralBase::Decimal {
-                        let n = number.parse::<f64>().expect("Should have been a valid float");
-                        tokens.push(Token::new(line, line_index, token_start, TokenType::NumberLiteral(n)))
-                    }
-                    else {
-                        // Parse string to number
-                        let n = num::BigInt::from_str_radix(&number, base.get_radix()).expect("Should have been a valid bigint");
-                        let n = n.to_f64().unwrap_or(f64::INFINITY);
+        let s = s.into_boxed_str();
+        let token = match (opening, substitution_follows) {
+            ('`', false) => TokenType::TemplateNoSubstitution(s),
+            ('`', true) => TokenType::TemplateHead(s),
+            ('}', false) => TokenType::TemplateTail(s),
+            ('}', true) => TokenType::TemplateMiddle(s),
+            _ => TokenType::StringLiteral(s),
+        };
+        // Track which braces end substitutions
+        if opening == '}' {
+            self.template_braces.pop();
+        }
+        if substitution_follows {
+            self.template_braces.push(0);
+        }
+        // The token's location is where the string starts, even if it contains newlines
+        self.push_token(start, token);
+        Ok(())
+    }
 
-                        tokens.push(Token::new(line, line_index, token_start, TokenType::NumberLiteral(n)))
-                    }
-                }
+    /// Lexes an escape sequence in a string, after the '\\' at `backslash`.  
+    /// Returns the char it represents, or `None` for a line continuation.
+    fn lex_escape(&mut self, backslash: Mark, quote: char) -> Result<Option<char>, LexError> {
+        let escape_start = self.mark();
+        let Some(c) = self.advance() else {
+            return Err(self.error(escape_start, LexErrorType::UnclosedString(quote)));
+        };
 
-                // Newline
-                '\n' => {
-                    tokens.push(Token::new(line, line_index, token_start, TokenType::NewLine));
-                    i += 1;
-                    line += 1;
-                    line_index = i;
-                }       
-                
-                // Ignore whitespace
-                w if w.is_whitespace() => {i += 1}
-
-                // Single line comments
-                '/' if program.get(i + 1) == Some(&'/') => {
-                    i += 2;
-                    // Find newline to end comment
-                    'comment: loop {
-                        match program.get(i) {
-                            None => break 'tokens,
-                            Some('\n') => {
-                                break 'comment
-                            },
-                            _ => i += 1,
+        let c = match c {
+            // Line continuation
+            c if is_line_terminator(c) => {
+                self.new_line();
+                return Ok(None);
+            }
+            // Newline
+            'n' => '\n',
+            // Carriage return
+            'r' => '\r',
+            // Tab
+            't' => '\t',
+            // Backspace
+            'b' => '\u{0008}',
+            // Form feed
+            'f' => '\u{000C}',
+            // Vertical tab
+            'v' => '\u{000B}',
+            // Null character, only if not followed by a digit
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => '\0',
+            // Legacy octal escape e.g. '\101' meaning 'A'
+            d @ '0'..='7' => {
+                if quote == '`' || !self.config.annex_b || self.config.strict {
+                    return Err(self.error(escape_start, LexErrorType::OctalEscape));
+                }
+                // Escapes starting with 0-3 can have up to 3 digits, others only up to 2
+                // This means the value is always less than 256
+                let max_digits = if d <= '3' {3} else {2};
+                let mut value = d.to_digit(8).expect("Should have been an octal digit");
+                for _ in 1..max_digits {
+                    match self.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            self.advance();
                         }
+                        None => break,
                     }
                 }
-
-                // Multi-line comments
-                '/' if program.get(i + 1) == Some(&'*') => {
-                    i += 2;
-                    // Find '*/' to end comment
-                    'comment: loop {
-                        match program.get(i) {
-                            None => break 'tokens,
-                            // Still track line / columns in a comment
-                            Some('\n') => {
-                                i += 1;
-                                line += 1;
-                                line_index = i;
-                            }
-                            Some('*') if program.get(i + 1) == Some(&'/') => {
-                                i += 2;
-                                break 'comment
-                            },
-                            _ => i += 1,
+                char::from_u32(value).expect("Should have been a valid char")
+            }
+            // '\8' and '\9' just mean '8' and '9'
+            d @ ('8' | '9') => {
+                if quote == '`' || !self.config.annex_b || self.config.strict {
+                    return Err(self.error(escape_start, LexErrorType::NonOctalDecimalEscape));
+                }
+                d
+            }
+            // Hex escape e.g. '\x41' meaning 'A'
+            'x' => {
+                let Some(value) = self.program.get(self.i..self.i + 2).and_then(parse_hex) else {
+                    return Err(self.error(backslash, LexErrorType::MalformedHexEscape));
+                };
+                self.i += 2;
+                char::from_u32(value).expect("Should have been a valid char")
+            }
+            // Unicode escape e.g. 'A' or '\u{41}' meaning 'A'
+            'u' => {
+                let (mut value, len) = read_unicode_escape(&self.program[self.i..]).map_err(|e| self.error(backslash, e))?;
+                self.i += len;
+                // Combine surrogate pairs written as two escapes e.g. '😀'
+                if (0xD800..0xDC00).contains(&value) && self.program[self.i..].starts_with("\\u") {
+                    if let Ok((low, len)) = read_unicode_escape(&self.program[self.i + 2..]) {
+                        if (0xDC00..0xE000).contains(&low) {
+                            value = 0x10000 + ((value - 0xD800) << 10) + (low - 0xDC00);
+                            self.i += 2 + len;
                         }
                     }
                 }
+                // Rust strings can't hold lone surrogates, so they are replaced
+                char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER)
+            }
+            // Any other character
+            c => c,
+        };
+        Ok(Some(c))
+    }
 
-                // An identifier
-                c if is_identifier_start(c) => {
-                    'chars_in_identifer: loop {
-                        match program.get(i) {
-                            None => {break 'chars_in_identifer},
-                            Some(&c) if is_identifier_continue(c) => i += 1,
-                            _ => break 'chars_in_identifer,
+    /// Lexes a Number or BigInt literal
+    fn lex_number(&mut self, start: Mark) -> Result<(), LexError> {
+        // Init base to decimal
+        let mut base = NumberLiteralBase::Decimal;
+        // Whether the literal has a leading '0' and no prefix e.g. '012', where separators aren't allowed
+        let mut legacy = false;
+        // Could be '0' literal, octal string e.g. '012' meaning 10, or start of '0x', '0b', etc.
+        if self.advance() == Some('0') {
+            let next = self.mark();
+            match self.peek() {
+                // If EOF here, generate `NumberLiteral(0)`
+                None => {
+                    self.push_token(start, TokenType::NumberLiteral(0.0));
+                    return Ok(());
+                },
+                Some(c) => base = match c {
+                    // Hex literal
+                    'x' | 'X' => {self.advance(); NumberLiteralBase::Hex},
+                    // Octal literal
+                    'o' | 'O' => {self.advance(); NumberLiteralBase::Octal},
+                    // Binary literal
+                    'b' | 'B' => {self.advance(); NumberLiteralBase::Binary},
+                    // Bigint '0n' literal
+                    'n' => {
+                        // Error if the next char is an identifier or digit
+                        if self.peek_next().is_some_and(|c| is_identifier_start(c) || c.is_ascii_digit()) {
+                            return Err(self.error(next, LexErrorType::IdentifierAfterNumber));
                         }
+                        // Generate `BigIntLiteral(0)` token
+                        self.advance();
+                        self.push_token(start, TokenType::BigIntLiteral(Box::new(BigInt::from(0))));
+                        return Ok(());
+                    },
+                    // Legacy literal with a leading '0' and no '0o' or '0O'
+                    '0'..='9' => {
+                        if self.config.strict {
+                            return Err(self.error(start, LexErrorType::LegacyOctalLiteral));
+                        }
+                        legacy = true;
+                        // If any digit is '8' or '9', the literal is decimal e.g. '089' meaning 89
+                        if self.program[self.i..].bytes().take_while(u8::is_ascii_digit).any(|b| b >= b'8') {
+                            NumberLiteralBase::Decimal
+                        }
+                        // Otherwise it is octal e.g. '012' meaning 10
+                        else {
+                            self.warn(start, WarningType::ImplicitOctal);
+                            NumberLiteralBase::Octal
+                        }
+                    },
+                    // A separator can't follow a leading '0' e.g. '0_1'
+                    '_' => return Err(self.error(next, LexErrorType::InvalidNumericSeparator)),
+                    // Error if identifier encountered
+                    c if is_identifier_start(c) => {
+                        return Err(self.error(next, LexErrorType::IdentifierAfterNumber));
+                    }
+                    // A decimal fraction e.g. '0.5', so go back to add the '0' to the number
+                    '.' => {
+                        self.i = start.index;
+                        NumberLiteralBase::Decimal
                     }
+                    _ => {
+                        self.push_token(start, TokenType::NumberLiteral(0.0));
+                        return Ok(());
+                    }
+                }
+            };
+        }
+        else {
+            // Go back to the first digit, so that it is added to the number
+            self.i = start.index;
+        }
 
-                    let ident: String = program[token_start..i].iter().collect();
+        // Whether there has been a decimal point yet
+        let mut had_decimal = false;
+        // The number for the string
+        let mut number = String::new();
+        // Whether the last char was a digit, which a separator must come after
+        let mut after_digit = false;
 
-                    tokens.push(Token::new(line, line_index, token_start, TokenType::Identifier(ident)));
-                }
-            
-                // Any other character: should be an operator
-                c => {
-                    for (operator, operator_token) in OPERATORS {
-                        // Get operators
-                        if program[i..i+operator.len()].iter().map(char::to_owned).eq(operator.chars()) {
-                            i += operator.len();
-                            tokens.push(Token::new(line, line_index, token_start, operator_token));
-                            continue 'tokens;
-                        }
+        'digits: loop {
+            let position = self.mark();
+            match self.peek() {
+                // Indicates a BigInt literal instead of a number
+                Some('n') => {
+                    if number.is_empty() {
+                        return Err(self.error(position, LexErrorType::MissingDigits(base)));
+                    }
+                    // BigInts can't have a decimal point or a leading '0' e.g. '012n'
+                    if had_decimal || legacy {
+                        return Err(self.error(position, LexErrorType::IdentifierAfterNumber));
                     }
-                    return Err(LexError::new(line, line_index, token_start, LexErrorType::InvalidChar(c)));
+                    self.advance();
+                    if self.peek().is_some_and(|c| is_identifier_start(c) || c.is_ascii_digit()) {
+                        return Err(self.error(self.mark(), LexErrorType::IdentifierAfterNumber));
+                    }
+                    let n = BigInt::from_str_radix(&number, base.get_radix()).expect("Should have been a valid bigint");
+                    self.push_token(start, TokenType::BigIntLiteral(Box::new(n)));
+                    return Ok(());
+                },
+                // A digit
+                Some(digit) if base.get_chars().contains(digit) => number.push(digit),
+                // Separators are ignored, but must be between two digits e.g. '1_000'
+                Some('_') => {
+                    let before_digit = self.peek_next().is_some_and(|c| base.get_chars().contains(c));
+                    if legacy || !after_digit || !before_digit {
+                        return Err(self.error(position, LexErrorType::InvalidNumericSeparator));
+                    }
+                }
+                // A decimal point, if there hasn't already been one
+                Some('.') if base == NumberLiteralBase::Decimal && !had_decimal => {
+                    had_decimal = true;
+                    number.push('.');
+                }
+                // Error if an identifier, or a digit not in the base, is found e.g. '0b12'
+                Some(id) if is_identifier_start(id) || (id.is_ascii_digit() && !number.is_empty()) => {
+                    return Err(self.error(position, LexErrorType::IdentifierAfterNumber))
                 }
+                // Any other character means the end of the number
+                _ => break 'digits,
             }
+            after_digit = self.peek().is_some_and(|c| base.get_chars().contains(c));
+            self.advance();
+            self.check_token_length(start)?;
         }
-        Ok(tokens)
+
+        // Error if a prefix isn't followed by any digits e.g. '0x;' or '0b2'
+        if number.is_empty() {
+            return Err(self.error(self.mark(), LexErrorType::MissingDigits(base)));
+        }
+
+        if base == NumberLiteralBase::Decimal {
+            let n = number.parse::<f64>().expect("Should have been a valid float");
+            self.push_token(start, TokenType::NumberLiteral(n));
+        }
+        else {
+            // Parse string to number
+            let n = BigInt::from_str_radix(&number, base.get_radix()).expect("Should have been a valid bigint");
+            let n = n.to_f64().unwrap_or(f64::INFINITY);
+
+            self.push_token(start, TokenType::NumberLiteral(n));
+        }
+        Ok(())
+    }
+
+    /// Whether HTML-like comments can be used, which needs `Config::html_comments` and `Config::annex_b`,
+    /// and isn't allowed in strict mode
+    fn html_comments_allowed(&self) -> bool {
+        self.config.html_comments && self.config.annex_b && !self.config.strict
+    }
+
+    /// Lexes a single line comment starting with `opening`, up to but not including the newline
+    fn lex_line_comment(&mut self, start: Mark, opening: &str) {
+        self.i += opening.len();
+        // Find newline or EOF to end comment
+        while self.peek().is_some_and(|c| !is_line_terminator(c)) {
+            self.advance();
+        }
+        self.collect_comment(start, CommentKind::Line, &self.program[start.index + opening.len()..self.i]);
+    }
+
+    /// Lexes a multi-line comment
+    fn lex_block_comment(&mut self, start: Mark) {
+        self.i += 2;
+        // Find '*/' or EOF to end comment
+        let text_end = loop {
+            match self.advance() {
+                None => break self.i,
+                // Still track line / columns in a comment
+                Some(c) if is_line_terminator(c) => self.new_line(),
+                Some('*') if self.peek() == Some('/') => {
+                    self.advance();
+                    break self.i - 2;
+                },
+                _ => (),
+            }
+        };
+        self.collect_comment(start, CommentKind::Block, &self.program[start.index + 2..text_end]);
+    }
+
+    /// Lexes a regex literal
+    fn lex_regex(&mut self, start: Mark) -> Result<(), LexError> {
+        self.advance();
+        // Whether the current char is inside a '[...]' class, where '/' doesn't end the regex
+        let mut in_class = false;
+        'regex: loop {
+            let c = self.advance();
+            self.check_token_length(start)?;
+            match c {
+                // Error on EOF or newlines
+                None => return Err(self.error(start, LexErrorType::UnclosedRegExp)),
+                Some(c) if is_line_terminator(c) => return Err(self.error(start, LexErrorType::UnclosedRegExp)),
+                // Escaped chars can't end the regex or a class, but can't be newlines
+                // The guard consumes the escaped char
+                Some('\\') if self.advance().is_none_or(is_line_terminator) => return Err(self.error(start, LexErrorType::UnclosedRegExp)),
+                Some('[') => in_class = true,
+                Some(']') => in_class = false,
+                Some('/') if !in_class => break 'regex,
+                _ => (),
+            }
+        }
+        let pattern = self.program[start.index + 1..self.i - 1].to_string();
+
+        // Flags are any identifier characters after the closing '/'
+        let flags_start = self.i;
+        while self.peek().is_some_and(is_identifier_continue) {
+            self.advance();
+            self.check_token_length(start)?;
+        }
+        let flags = self.program[flags_start..self.i].to_string();
+
+        self.push_token(start, TokenType::RegExpLiteral(Box::new(RegExp { pattern, flags })));
+        Ok(())
     }
-}
\ No newline at end of file
+
+    /// Lexes an identifier, keyword, or private identifier
+    fn lex_identifier(&mut self, start: Mark) -> Result<(), LexError> {
+        // Skip the '#' of a private identifier
+        let private = self.peek() == Some('#');
+        if private {
+            self.advance();
+        }
+        let name_start = self.i;
+
+        while self.peek().is_some_and(is_identifier_continue) {
+            self.advance();
+            self.check_token_length(start)?;
+        }
+
+        let ident = &self.program[name_start..self.i];
+
+        let token = if private {
+            TokenType::PrivateIdentifier(self.interner.intern(ident))
+        }
+        else {
+            // Reserved words are keywords, but contextual keywords are still identifiers
+            // In strict mode, words such as `implements` are also reserved
+            match Keyword::from_name(ident) {
+                Some(keyword) if !keyword.is_contextual() || (self.config.strict && keyword.is_strict_reserved()) => TokenType::Keyword(keyword),
+                _ => TokenType::Identifier(self.interner.intern(ident)),
+            }
+        };
+
+        self.push_token(start, token);
+        Ok(())
+    }
+
+    /// Lexes an operator or punctuation, starting with `c`
+    fn lex_operator(&mut self, start: Mark, c: char) -> Result<(), LexError> {
+        let rest = &self.program[self.i..];
+        // `?.` followed by a digit is a conditional with a number e.g. `a?.5:b`
+        let is_match = |operator: &str| rest.starts_with(operator) && !(operator == "?." && rest[2..].starts_with(|c: char| c.is_ascii_digit()));
+        let Some((operator, operator_token)) = OPERATORS.iter().find(|(operator, _)| is_match(operator)) else {
+            return Err(self.error(start, LexErrorType::InvalidChar(c)));
+        };
+
+        self.i += operator.len();
+        // Track which parens are conditions of control statements
+        let control_paren = self.control_paren_next;
+        self.push_token(start, operator_token.clone());
+        match operator_token {
+            TokenType::OpenParen => self.control_parens.push(control_paren),
+            // A statement follows the condition rather than an operator, so `/` starts a regex
+            TokenType::CloseParen if self.control_parens.pop().flatten().is_some() => self.goal = LexGoal::RegExp,
+            _ => (),
+        }
+        // Track braces within template substitutions
+        match (operator_token, self.template_braces.last_mut()) {
+            (TokenType::OpenBrace, Some(n)) => *n += 1,
+            (TokenType::CloseBrace, Some(n)) => *n -= 1,
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for TokenStream<'_> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Lex until there is a token, as whitespace and comments don't produce one
+        while self.next_token.is_none() && !self.finished {
+            if let Err(e) = self.lex_next() {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        }
+        self.next_token.take().map(Ok)
+    }
+}
+
+/// Lexes a program with the given config, and gets the types of its tokens
+#[cfg(test)]
+fn lex_tokens_with(config: Config, s: &str) -> Vec<TokenType> {
+    Lexer::new(config).lex(s).unwrap().into_iter().map(|t| t.token).collect()
+}
+
+/// Lexes a program with the default config, and gets the types of its tokens
+#[cfg(test)]
+fn lex_tokens(s: &str) -> Vec<TokenType> {
+    lex_tokens_with(Config::default(), s)
+}
+
+/// Lexes a program starting with a string or template literal with the given config, and gets its value
+#[cfg(test)]
+fn lex_string_with(config: Config, s: &str) -> Result<String, LexErrorType> {
+    let tokens = Lexer::new(config).lex(s).map_err(|e| e.error_type)?;
+    match &tokens[0].token {
+        TokenType::StringLiteral(s) | TokenType::TemplateNoSubstitution(s) => Ok(s.to_string()),
+        t => panic!("Expected string literal, got {t:?}"),
+    }
+}
+
+#[test]
+/// Tests that legacy octal and `\8`/`\9` escapes are only allowed with `Config::annex_b`
+fn test_legacy_escapes() {
+    let lex_string = |s: &str, annex_b: bool| lex_string_with(Config { annex_b, ..Default::default() }, s);
+
+    assert_eq!(lex_string(r#""\0""#, false).unwrap(), "\0");
+    assert_eq!(lex_string(r#"`\0a`"#, false).unwrap(), "\0a");
+
+    assert_eq!(lex_string(r#""\101\7\08\400\3777""#, true).unwrap(), "A\u{7}\u{0}8\u{20}0\u{FF}7");
+    assert_eq!(lex_string(r#"'\8\9'"#, true).unwrap(), "89");
+
+    assert!(matches!(lex_string(r#""\01""#, false), Err(LexErrorType::OctalEscape)));
+    assert!(matches!(lex_string(r#""\7""#, false), Err(LexErrorType::OctalEscape)));
+    assert!(matches!(lex_string(r#"`\1`"#, true), Err(LexErrorType::OctalEscape)));
+    assert!(matches!(lex_string(r#""\8""#, false), Err(LexErrorType::NonOctalDecimalEscape)));
+    assert!(matches!(lex_string(r#"`\9`"#, true), Err(LexErrorType::NonOctalDecimalEscape)));
+}
+
+#[test]
+/// Tests that implicit octal literals produce a warning
+fn test_implicit_octal_warning() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::engine::diagnostics::DiagnosticSink;
+
+    #[derive(Debug, Default)]
+    struct Collector(RefCell<Vec<Warning>>);
+    impl DiagnosticSink for Collector {
+        fn warn(&self, warning: Warning) {
+            self.0.borrow_mut().push(warning);
+        }
+    }
+
+    let collector = Rc::new(Collector::default());
+    let config = Config { diagnostics: Some(collector.clone()), ..Default::default() };
+    Lexer::new(config).lex("0o7 0 10 012\n  007").unwrap();
+
+    let warnings = collector.0.borrow();
+    assert_eq!(warnings.len(), 2);
+    assert!(matches!(warnings[0], Warning { line: 1, char: 10, warning_type: WarningType::ImplicitOctal, .. }));
+    assert!(matches!(warnings[1], Warning { line: 2, char: 3, warning_type: WarningType::ImplicitOctal, .. }));
+}
+
+#[test]
+/// Tests that '/' is lexed as division or as the start of a regex depending on the previous token
+fn test_regex_or_division() {
+    assert!(matches!(lex_tokens("a / b /= c")[..], [
+        TokenType::Identifier(_), TokenType::OperatorDivision, TokenType::Identifier(_), TokenType::OperatorDivisionAssignment, TokenType::Identifier(_)
+    ]));
+    assert!(matches!(lex_tokens("(1) / 2")[..], [.., TokenType::CloseParen, TokenType::OperatorDivision, TokenType::NumberLiteral(_)]));
+    assert!(matches!(lex_tokens("x.return / 2")[..], [.., TokenType::OperatorDivision, TokenType::NumberLiteral(_)]));
+
+    match &lex_tokens("a.match(/[/\\]]+/gi)")[..] {
+        [.., TokenType::OpenParen, TokenType::RegExpLiteral(regex), TokenType::CloseParen] => {
+            assert_eq!(regex.pattern, "[/\\]]+");
+            assert_eq!(regex.flags, "gi");
+        }
+        t => panic!("Expected regex literal, got {t:?}"),
+    }
+    assert!(matches!(lex_tokens("return /a/")[..], [TokenType::Keyword(Keyword::Return), TokenType::RegExpLiteral(_)]));
+    assert!(matches!(lex_tokens("for (a of /a/g)")[..], [.., TokenType::Identifier(_), TokenType::RegExpLiteral(_), TokenType::CloseParen]));
+    assert!(matches!(lex_tokens("this / 2")[..], [TokenType::Keyword(Keyword::This), TokenType::OperatorDivision, TokenType::NumberLiteral(_)]));
+    assert!(matches!(lex_tokens("x = 1;\n/=/.test(y)")[..], [_, _, _, TokenType::Semicolon, TokenType::NewLine, TokenType::RegExpLiteral(_), TokenType::OperatorDot, ..]));
+
+    assert!(matches!(Lexer::default().lex("/abc\n/").map_err(|e| e.error_type), Err(LexErrorType::UnclosedRegExp)));
+
+    // A statement rather than an operator follows the condition of a control statement
+    assert!(matches!(lex_tokens("if (a) /b/.test(c)")[..], [.., TokenType::CloseParen, TokenType::RegExpLiteral(_), TokenType::OperatorDot, _, _, _, _]));
+    assert!(matches!(lex_tokens("while ((a) / 2)\n/b/g")[..], [.., TokenType::OperatorDivision, _, TokenType::CloseParen, TokenType::NewLine, TokenType::RegExpLiteral(_)]));
+    assert!(matches!(lex_tokens("for await (x of y) /a/")[..], [.., TokenType::CloseParen, TokenType::RegExpLiteral(_)]));
+    assert!(matches!(lex_tokens("f(if_) / 2")[..], [.., TokenType::CloseParen, TokenType::OperatorDivision, TokenType::NumberLiteral(_)]));
+    // Keywords after a '.' are property names, so their parens aren't conditions
+    for program in ["a.if(x) / 2", "obj.for(k) / 2", "a.with(b) / 2", "o.for await (x) / 2"] {
+        assert!(matches!(lex_tokens(program)[..], [.., TokenType::CloseParen, TokenType::OperatorDivision, TokenType::NumberLiteral(_)]), "for {program:?}");
+    }
+    assert!(matches!(lex_tokens("x.while(y) / z / w")[..], [.., TokenType::CloseParen, TokenType::OperatorDivision, _, TokenType::OperatorDivision, _]));
+
+    // Contextual keywords used as identifiers are followed by division
+    for program in ["of / 2", "await / 2", "yield / 2", "for (a in of / 2) x", "for (a of b.of / 2) x"] {
+        let tokens = lex_tokens(program);
+        assert!(tokens.iter().any(|t| matches!(t, TokenType::OperatorDivision)), "for {program:?}: {tokens:?}");
+    }
+}
+
+#[test]
+/// Tests that tokens longer than `Config::max_token_length` cause an error at the start of the token
+fn test_max_token_length() {
+    let lex = |s: &str| Lexer::new(Config { max_token_length: Some(5), ..Default::default() }).lex(s);
+
+    assert!(lex("/abc/ abcde '123' 12345").is_ok());
+    for program in ["x = abcdef", "x = '1234'", "x = 123456", "x = /abcd/"] {
+        assert!(matches!(lex(program), Err(LexError { line: 1, char: 5, error_type: LexErrorType::TokenTooLong(5), .. })), "{program}");
+    }
+}
+
+#[test]
+/// Tests that template literals are split into tokens around substitutions
+fn test_template_literals() {
+    assert!(matches!(&lex_tokens("`a\\`b`")[..], [TokenType::TemplateNoSubstitution(s)] if &**s == "a`b"));
+    assert!(matches!(&lex_tokens("`$a{b}$`")[..], [TokenType::TemplateNoSubstitution(s)] if &**s == "$a{b}$"));
+
+    match &lex_tokens("`a ${x} b ${ {y: `c${z}`}.y } d`")[..] {
+        [
+            TokenType::TemplateHead(a), TokenType::Identifier(_), TokenType::TemplateMiddle(b),
+            TokenType::OpenBrace, TokenType::Identifier(_), TokenType::OperatorColon,
+            TokenType::TemplateHead(c), TokenType::Identifier(_), TokenType::TemplateTail(empty),
+            TokenType::CloseBrace, TokenType::OperatorDot, TokenType::Identifier(_), TokenType::TemplateTail(d),
+        ] => {
+            assert_eq!([a, b, c, empty, d].map(|s| &**s), ["a ", " b ", "c", "", " d"]);
+        }
+        t => panic!("Unexpected tokens {t:?}"),
+    }
+
+    assert!(matches!(Lexer::default().lex("`${a"), Err(LexError { error_type: LexErrorType::UnclosedString('`'), .. })));
+
+    // A regex can't follow the end of a template, but can follow the start of a substitution
+    assert!(matches!(lex_tokens("`${/a/}` / 2")[..], [
+        TokenType::TemplateHead(_), TokenType::RegExpLiteral(_), TokenType::TemplateTail(_), TokenType::OperatorDivision, TokenType::NumberLiteral(_)
+    ]));
+}
+
+#[test]
+/// Tests `\x` and `\u` escape sequences in strings
+fn test_hex_and_unicode_escapes() {
+    assert_eq!(lex_string_with(Config::default(), r#""\x41\x7a\u0042\u{43}\u{000000044}""#).unwrap(), "AzBCD");
+    assert_eq!(lex_string_with(Config::default(), r#"`\u{1F600}\uD83D\uDE00`"#).unwrap(), "\u{1F600}\u{1F600}");
+    assert_eq!(lex_string_with(Config::default(), r#"'\uD83D!'"#).unwrap(), "\u{FFFD}!");
+
+    assert!(matches!(lex_string_with(Config::default(), r#""\x4""#), Err(LexErrorType::MalformedHexEscape)));
+    assert!(matches!(lex_string_with(Config::default(), r#""\xG0""#), Err(LexErrorType::MalformedHexEscape)));
+    assert!(matches!(lex_string_with(Config::default(), r#""\u004""#), Err(LexErrorType::MalformedUnicodeEscape)));
+    assert!(matches!(lex_string_with(Config::default(), r#""\u{}""#), Err(LexErrorType::MalformedUnicodeEscape)));
+    assert!(matches!(lex_string_with(Config::default(), r#""\u{41""#), Err(LexErrorType::MalformedUnicodeEscape)));
+    assert!(matches!(lex_string_with(Config::default(), r#""\u{110000}""#), Err(LexErrorType::UnicodeEscapeOutOfRange)));
+    assert!(matches!(lex_string_with(Config::default(), r#""\u{FFFFFFFFFF}""#), Err(LexErrorType::UnicodeEscapeOutOfRange)));
+}
+
+#[test]
+/// Tests that a hashbang is only skipped at the start of the program
+fn test_hashbang() {
+    let tokens = Lexer::default().lex("#!/usr/bin/env js\nx").unwrap();
+    assert!(matches!(&tokens[..], [
+        Token { token: TokenType::NewLine, line: 1, char: 18, .. },
+        Token { token: TokenType::Identifier(_), line: 2, char: 1, .. },
+    ]));
+    assert!(Lexer::default().lex("#!").unwrap().is_empty());
+
+    assert!(matches!(Lexer::default().lex(" #!/usr/bin/env js"), Err(LexError { error_type: LexErrorType::InvalidChar('#'), .. })));
+    assert!(matches!(Lexer::default().lex("x\n#!/usr/bin/env js"), Err(LexError { error_type: LexErrorType::InvalidChar('#'), .. })));
+}
+
+#[test]
+/// Tests that private identifiers are lexed without their '#'
+fn test_private_identifiers() {
+    let tokens = Lexer::default().lex("this.#x / 2; #if in obj").unwrap();
+    assert!(matches!(&tokens[..], [
+        _, _, Token { token: TokenType::PrivateIdentifier(x), char: 6, .. }, Token { token: TokenType::OperatorDivision, .. }, _, _,
+        Token { token: TokenType::PrivateIdentifier(keyword), .. }, Token { token: TokenType::Keyword(Keyword::In), .. }, _,
+    ] if x == "x" && keyword == "if"));
+
+    assert!(matches!(Lexer::default().lex("# x"), Err(LexError { error_type: LexErrorType::InvalidChar('#'), .. })));
+    assert!(matches!(Lexer::default().lex("#1"), Err(LexError { error_type: LexErrorType::InvalidChar('#'), .. })));
+}
+
+#[test]
+/// Tests that comments are collected and attached to the right tokens
+fn test_comment_trivia() {
+    let mut lexer = Lexer::new(Config { collect_comments: true, ..Default::default() });
+    let program = "// leading\n\na /* trailing */ b // trailing\n/* a\nb */\nc\n// end";
+    let tokens = public::to_public(&lexer.lex(program).unwrap(), program);
+    let comments: Vec<_> = lexer.comments().iter().map(|c| (c.kind, c.text.as_str(), c.line, c.char, c.token, c.trailing)).collect();
+
+    // Comments refer to tokens by their index in the public tokens, which don't include newlines
+    let texts: Vec<_> = tokens.iter().map(|t| t.text.as_str()).collect();
+    assert_eq!(texts, ["a", "b", "c"]);
+    assert_eq!(comments, [
+        (CommentKind::Line, " leading", 1, 1, 0, false),
+        (CommentKind::Block, " trailing ", 3, 3, 0, true),
+        (CommentKind::Line, " trailing", 3, 20, 1, true),
+        (CommentKind::Block, " a\nb ", 4, 1, 2, false),
+        (CommentKind::Line, " end", 7, 1, 3, false),
+    ]);
+
+    assert!(Lexer::default().lex("// comment").is_ok_and(|t| t.is_empty()));
+}
+
+#[test]
+/// Tests that token spans are byte offsets while columns are counted in chars
+fn test_byte_offsets() {
+    let tokens = Lexer::default().lex("'héllo' + 1.5\nπ = 'é'").unwrap();
+    let positions: Vec<_> = tokens.iter().map(|t| (t.line, t.char, t.span.start)).collect();
+    assert_eq!(positions, [(1, 1, 0), (1, 9, 9), (1, 11, 11), (1, 14, 14), (2, 1, 15), (2, 3, 18), (2, 5, 20)]);
+    assert!(matches!(tokens[2].token, TokenType::NumberLiteral(n) if n == 1.5));
+    assert_eq!(tokens[0].span, Span::new(0, 8));
+    assert_eq!(&"'héllo' + 1.5"[std::ops::Range::from(tokens[2].span)], "1.5");
+
+    let error = Lexer::default().lex("'é'  @").unwrap_err();
+    assert_eq!((error.char, error.index), (6, 6));
+}
+
+#[test]
+/// Tests that <LS> and <PS> are line terminators
+fn test_unicode_line_terminators() {
+    let tokens = Lexer::default().lex("a\u{2028}b // c\u{2029}'d\u{2028}e'\n`\u{2029}`").unwrap();
+    let lines: Vec<_> = tokens.iter().map(|t| (t.line, t.char)).collect();
+    assert_eq!(lines, [(1, 1), (1, 2), (2, 1), (2, 7), (3, 1), (4, 3), (5, 1)]);
+    assert!(matches!(&tokens[4].token, TokenType::StringLiteral(s) if &**s == "d\u{2028}e"));
+
+    assert!(matches!(Lexer::default().lex("x = /a\u{2028}/").map_err(|e| e.error_type), Err(LexErrorType::UnclosedRegExp)));
+    assert!(matches!(&Lexer::default().lex("'a\\\u{2029}b'").unwrap()[0].token, TokenType::StringLiteral(s) if &**s == "ab"));
+}
+
+#[test]
+/// Tests that '\r\n' and '\r' are each a single line terminator
+fn test_crlf() {
+    let tokens = Lexer::default().lex("a\r\nb\rc /* \r\n */ d\r\n`e\r\nf\rg` 'h\\\r\ni'").unwrap();
+    let lines: Vec<_> = tokens.iter().map(|t| (t.line, t.char, &t.token)).collect();
+    assert!(matches!(lines[..], [
+        (1, 1, _), (1, 2, TokenType::NewLine), (2, 1, _), (2, 2, TokenType::NewLine), (3, 1, _), (4, 5, _), (4, 6, TokenType::NewLine),
+        (5, 1, TokenType::TemplateNoSubstitution(t)), (7, 4, TokenType::StringLiteral(s)),
+    ] if &**t == "e\nf\ng" && &**s == "hi"));
+    assert_eq!(tokens.len(), 9);
+
+    assert!(matches!(Lexer::default().lex("'a\rb'").map_err(|e| e.error_type), Err(LexErrorType::NewlineInString('\''))));
+}
+
+#[test]
+/// Tests that a token stream lexes tokens as they are needed, and ends after an error
+fn test_token_stream() {
+    let mut lexer = Lexer::default();
+    let program = "if (a) /b/.test(c) // d\n@ e";
+    let mut stream = lexer.stream(program);
+    assert!(matches!(stream.next(), Some(Ok(Token { token: TokenType::Keyword(Keyword::If), .. }))));
+
+    let streamed: Vec<_> = stream.by_ref().take_while(Result::is_ok).map(Result::unwrap).collect();
+    let lexed = Lexer::default().lex(&program[..program.find('@').unwrap()]).unwrap();
+    assert_eq!(format!("{streamed:?}"), format!("{:?}", &lexed[1..]));
+    assert!(stream.next().is_none());
+
+    assert!(matches!(lexer.stream("`${").last(), Some(Err(LexError { error_type: LexErrorType::UnclosedString('`'), .. }))));
+}
+
+#[test]
+/// Tests that `Config::strict` rejects legacy octal syntax and reserves strict mode keywords
+fn test_strict_mode() {
+    let strict = || Lexer::new(Config { strict: true, ..Default::default() });
+
+    assert!(matches!(strict().lex("x = 012").map_err(|e| e.error_type), Err(LexErrorType::LegacyOctalLiteral)));
+    assert!(matches!(strict().lex("x = 08").map_err(|e| e.error_type), Err(LexErrorType::LegacyOctalLiteral)));
+    assert!(matches!(strict().lex("'\\07'").map_err(|e| e.error_type), Err(LexErrorType::OctalEscape)));
+    assert!(matches!(strict().lex("'\\8'").map_err(|e| e.error_type), Err(LexErrorType::NonOctalDecimalEscape)));
+    let tokens = strict().lex("0 0.5 0o12 '\\0'").unwrap();
+    assert_eq!(format!("{:?}", tokens.iter().map(|t| &t.token).collect::<Vec<_>>()), r#"[NumberLiteral(0.0), NumberLiteral(0.5), NumberLiteral(10.0), StringLiteral("\0")]"#);
+
+    assert!(matches!(strict().lex("implements").unwrap()[0].token, TokenType::Keyword(Keyword::Implements)));
+    for (name, keyword) in [("let", Keyword::Let), ("static", Keyword::Static), ("yield", Keyword::Yield)] {
+        assert!(matches!(strict().lex(name).unwrap()[0].token, TokenType::Keyword(k) if k == keyword), "for {name:?}");
+    }
+    assert!(matches!(&strict().lex("of").unwrap()[0].token, TokenType::Identifier(i) if i == "of"));
+    assert!(matches!(strict().lex("yield /a/").unwrap()[1].token, TokenType::RegExpLiteral(_)));
+    assert!(matches!(&Lexer::default().lex("public").unwrap()[0].token, TokenType::Identifier(i) if i == "public"));
+
+    // Outside strict mode, a leading '0' with an '8' or '9' is decimal
+    let tokens = Lexer::default().lex("012 089 08.5 0.5 0.").unwrap();
+    let values: Vec<_> = tokens.iter().map(|t| match t.token { TokenType::NumberLiteral(n) => n, _ => f64::NAN }).collect();
+    assert_eq!(values, [10.0, 89.0, 8.5, 0.5, 0.0]);
+}
+
+#[test]
+/// Tests that all of the spec's whitespace is skipped, along with a leading byte order mark
+fn test_unicode_whitespace() {
+    let tokens = Lexer::default().lex("\u{FEFF}#!/usr/bin/env js\na\u{00A0}b\u{2009}c\u{FEFF}d\u{3000}").unwrap();
+    assert!(matches!(&tokens[..], [
+        Token { token: TokenType::NewLine, .. }, Token { token: TokenType::Identifier(_), char: 1, .. }, Token { char: 3, .. }, Token { char: 5, .. }, Token { char: 7, .. },
+    ]));
+
+    // A leading byte order mark doesn't count as a column, but one anywhere else does
+    let tokens = Lexer::default().lex("\u{FEFF}a \u{FEFF}b").unwrap();
+    assert!(matches!(&tokens[..], [Token { line: 1, char: 1, .. }, Token { line: 1, char: 4, .. }]));
+    assert_eq!(tokens[0].span, Span::new(3, 4));
+    assert!(matches!(Lexer::default().lex("\u{FEFF}'a"), Err(LexError { line: 1, char: 3, .. })));
+
+    // <NEL> is whitespace in Rust but not in JS
+    assert!(matches!(Lexer::default().lex("a\u{0085}b").map_err(|e| e.error_type), Err(LexErrorType::InvalidChar('\u{0085}'))));
+}
+
+#[test]
+/// Tests that HTML-like comments are only comments with `Config::html_comments` and outside strict mode
+fn test_html_comments() {
+    let lex = |s: &str, html_comments: bool, strict: bool, annex_b: bool| {
+        let config = Config { html_comments, strict, annex_b, ..Default::default() };
+        lex_tokens_with(config, s).into_iter().filter(|t| !matches!(t, TokenType::NewLine)).collect::<Vec<_>>()
+    };
+
+    let program = "a <!-- b\n  --> c\nx --> y\n/*\n*/ --> z";
+    assert!(matches!(lex(program, true, false, true)[..], [
+        TokenType::Identifier(_), TokenType::Identifier(_), TokenType::OperatorDecrement, TokenType::OperatorGreaterThan, TokenType::Identifier(_),
+    ]));
+    assert!(matches!(lex(program, false, false, true)[..], [
+        TokenType::Identifier(_), TokenType::OperatorLessThan, TokenType::OperatorLogicalNot, TokenType::OperatorDecrement, TokenType::Identifier(_), ..
+    ]));
+    // HTML-like comments are part of Annex B, so aren't allowed in strict mode or without `Config::annex_b`
+    assert_eq!(format!("{:?}", lex(program, true, true, true)), format!("{:?}", lex(program, false, false, true)));
+    assert_eq!(format!("{:?}", lex(program, true, false, false)), format!("{:?}", lex(program, false, false, true)));
+
+    // HTML-like comments are collected as line comments, without the `<!--` or `-->`
+    let mut lexer = Lexer::new(Config { html_comments: true, collect_comments: true, ..Default::default() });
+    lexer.lex(program).unwrap();
+    let comments: Vec<_> = lexer.comments().iter().map(|c| (c.kind, c.text.as_str(), &program[c.span.start..c.span.end])).collect();
+    assert_eq!(comments, [
+        (CommentKind::Line, " b", "<!-- b"), (CommentKind::Line, " c", "--> c"), (CommentKind::Block, "\n", "/*\n*/"), (CommentKind::Line, " z", "--> z"),
+    ]);
+}
+
+#[test]
+/// Tests that numeric separators are only allowed between two digits
+fn test_numeric_separators() {
+    let tokens = lex_tokens("1_000 0b1_0 1_0.5_5 0xA_bn");
+    let values: Vec<_> = tokens.iter().map(|t| format!("{t:?}")).collect();
+    assert_eq!(values, ["NumberLiteral(1000.0)", "NumberLiteral(2.0)", "NumberLiteral(10.55)", "BigIntLiteral(171)"]);
+
+    for program in ["1_", "1__0", "0x_1", "0b_", "1_.5", "1._5", "1_n", "0_1", "01_2", "08_1"] {
+        let e = Lexer::default().lex(program).map_err(|e| e.error_type);
+        assert!(matches!(e, Err(LexErrorType::InvalidNumericSeparator)), "for {program:?}: {e:?}");
+    }
+}
+
+#[test]
+/// Tests BigInt literals, which can't have a leading '0' or be followed directly by an identifier or digit
+fn test_bigint_literals() {
+    let tokens = lex_tokens("0n 123n 0x1Fn 0o17n 0b101n");
+    let values: Vec<_> = tokens.iter().map(|t| format!("{t:?}")).collect();
+    assert_eq!(values, ["BigIntLiteral(0)", "BigIntLiteral(123)", "BigIntLiteral(31)", "BigIntLiteral(15)", "BigIntLiteral(5)"]);
+
+    for program in ["012n", "08n", "0n1", "0na", "1n2", "1nx", "1.5n", "0b12", "0o78"] {
+        let e = Lexer::default().lex(program).map_err(|e| e.error_type);
+        assert!(matches!(e, Err(LexErrorType::IdentifierAfterNumber)), "for {program:?}: {e:?}");
+    }
+}
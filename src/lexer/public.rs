@@ -0,0 +1,150 @@
+//! A stable view of the lexer's tokens, for tools which want to reuse the lexer without running a program.
+
+use std::ops::Range;
+
+use super::{LexError, Lexer, Span, Token, TokenType};
+use crate::util::is_line_terminator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The broad kind of a `PublicToken`, as used by e.g. syntax highlighters
+pub enum TokenKind {
+    /// A variable or property name, including contextual keywords such as `let` and `of`
+    Identifier,
+    /// A class private name such as `#field`
+    PrivateIdentifier,
+    /// A reserved word such as `if`, including `true`, `false`, and `null`
+    Keyword,
+    /// A string literal in single or double quotes
+    String,
+    /// A template literal, or the part of one between substitutions
+    Template,
+    /// A number literal
+    Number,
+    /// A BigInt literal such as `1n`
+    BigInt,
+    /// A regular expression literal
+    RegExp,
+    /// An operator or punctuation, such as `+=` or `{`
+    Punctuator,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A token in a program
+pub struct PublicToken {
+    pub kind: TokenKind,
+    /// The source text of the token, including any quotes or slashes
+    pub text: String,
+    /// The byte range of the token in the program
+    pub span: Span,
+    /// The line of the start of the token, from 1
+    pub line: usize,
+    /// The column of the start of the token in chars, from 1
+    pub char: usize,
+    /// The byte range of the whitespace and comments between the previous token and this one
+    pub trivia: Span,
+    /// Whether there is a line terminator between this token and the next one, or the end of the program.
+    /// This includes line terminators in comments, and is what decides where semicolons are automatically inserted.
+    pub newline_after: bool,
+}
+
+impl PublicToken {
+    /// Converts a lexer token from `program`, or returns `None` for tokens which aren't public such as newlines
+    pub(crate) fn new(token: &Token, program: &str) -> Option<PublicToken> {
+        let kind = match &token.token {
+            TokenType::NewLine => return None,
+            TokenType::Identifier(_) => TokenKind::Identifier,
+            TokenType::PrivateIdentifier(_) => TokenKind::PrivateIdentifier,
+            TokenType::Keyword(_) => TokenKind::Keyword,
+            TokenType::StringLiteral(_) => TokenKind::String,
+            TokenType::TemplateNoSubstitution(_)
+            | TokenType::TemplateHead(_)
+            | TokenType::TemplateMiddle(_)
+            | TokenType::TemplateTail(_) => TokenKind::Template,
+            TokenType::NumberLiteral(_) => TokenKind::Number,
+            TokenType::BigIntLiteral(_) => TokenKind::BigInt,
+            TokenType::RegExpLiteral(_) => TokenKind::RegExp,
+            _ => TokenKind::Punctuator,
+        };
+
+        Some(PublicToken {
+            kind,
+            text: program[Range::from(token.span)].to_string(),
+            span: token.span,
+            line: token.line,
+            char: token.char,
+            trivia: Span::new(token.span.start, token.span.start),
+            newline_after: false,
+        })
+    }
+}
+
+/// Converts the tokens lexed from `program` to public tokens
+pub(crate) fn to_public(tokens: &[Token], program: &str) -> Vec<PublicToken> {
+    let mut public: Vec<PublicToken> = vec![];
+    for token in tokens.iter().filter_map(|t| PublicToken::new(t, program)) {
+        // Trivia is everything since the end of the previous token
+        let trivia = Span::new(public.last().map_or(0, |t| t.span.end), token.span.start);
+        if let Some(previous) = public.last_mut() {
+            previous.newline_after = program[Range::from(trivia)].contains(is_line_terminator);
+        }
+        public.push(PublicToken { trivia, ..token });
+    }
+
+    if let Some(last) = public.last_mut() {
+        last.newline_after = program[last.span.end..].contains(is_line_terminator);
+    }
+    public
+}
+
+/// Lexes a program with the default config, without parsing or running it.  
+/// Newlines are not included as tokens.
+pub fn tokenize(program: &str) -> Result<Vec<PublicToken>, LexError> {
+    let tokens = Lexer::default().lex(program)?;
+    Ok(to_public(&tokens, program))
+}
+
+#[test]
+/// Tests that public tokens have the right kind, text, and span
+fn test_tokenize() {
+    let tokens = tokenize("let s = `a${'é'}b`;/* c */\n/d/g.test(#x) + 1n").unwrap();
+    let tokens: Vec<_> = tokens.iter().map(|t| (t.kind, t.text.as_str(), Range::from(t.span), t.line)).collect();
+    assert_eq!(tokens, [
+        (TokenKind::Identifier, "let", 0..3, 1),
+        (TokenKind::Identifier, "s", 4..5, 1),
+        (TokenKind::Punctuator, "=", 6..7, 1),
+        (TokenKind::Template, "`a${", 8..12, 1),
+        (TokenKind::String, "'é'", 12..16, 1),
+        (TokenKind::Template, "}b`", 16..19, 1),
+        (TokenKind::Punctuator, ";", 19..20, 1),
+        (TokenKind::RegExp, "/d/g", 28..32, 2),
+        (TokenKind::Punctuator, ".", 32..33, 2),
+        (TokenKind::Identifier, "test", 33..37, 2),
+        (TokenKind::Punctuator, "(", 37..38, 2),
+        (TokenKind::PrivateIdentifier, "#x", 38..40, 2),
+        (TokenKind::Punctuator, ")", 40..41, 2),
+        (TokenKind::Punctuator, "+", 42..43, 2),
+        (TokenKind::BigInt, "1n", 44..46, 2),
+    ]);
+
+    assert!(tokenize("'abc").is_err());
+    // Half-typed numbers are errors rather than panics
+    for program in ["0x;", "0b2", "0o9", "0x", "0xn"] {
+        let e = tokenize(program).unwrap_err();
+        assert!(matches!(e.error_type, super::LexErrorType::MissingDigits(_)), "for {program:?}");
+    }
+    assert_eq!(tokenize("0x;").unwrap_err().to_string(), "SyntaxError: missing hexadecimal digits after '0x'");
+}
+
+#[test]
+/// Tests that public tokens know their trivia and whether a newline follows them
+fn test_newline_after() {
+    let program = "a /* x */ b /*\n*/ c // y\nd\u{2028}";
+    let tokens = tokenize(program).unwrap();
+    let tokens: Vec<_> = tokens.iter().map(|t| (&program[Range::from(t.trivia)], t.newline_after)).collect();
+    assert_eq!(tokens, [("", false), (" /* x */ ", true), (" /*\n*/ ", true), (" // y\n", true)]);
+
+    // Spans can be compared with the span helpers
+    let tokens = tokenize(program).unwrap();
+    assert!(tokens[0].span < tokens[1].span && !tokens[1].span.overlaps(tokens[1].trivia));
+    assert!(tokens[0].span.join(tokens[2].span).contains_span(tokens[1].trivia));
+}
@@ -0,0 +1,75 @@
+use std::fmt::Debug;
+use std::ops::Range;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A range of bytes in a program, such as the source text of a token.
+/// Spans are ordered by their start, then by their end.
+pub struct Span {
+    /// The byte index of the start of the range
+    pub start: usize,
+    /// The byte index just after the end of the range
+    pub end: usize,
+}
+
+impl Span {
+    #[inline]
+    pub const fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// Whether the span contains no bytes
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    /// Whether the byte at `index` is inside the span
+    #[inline]
+    pub const fn contains(&self, index: usize) -> bool {
+        self.start <= index && index < self.end
+    }
+
+    /// Whether `other` is entirely inside the span
+    #[inline]
+    pub const fn contains_span(&self, other: Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Whether the two spans have at least one byte in common
+    #[inline]
+    pub const fn overlaps(&self, other: Span) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// The smallest span containing both spans, e.g. from the first token of a statement to the last
+    #[inline]
+    pub fn join(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// Impl of `Debug` for `Span`.
+/// This is written like a `Range` to keep token dumps short.
+impl Debug for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}..{}", self.start, self.end))
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Range<usize> {
+        span.start..span.end
+    }
+}
+
+#[test]
+/// Tests ordering, containment, and overlap of spans
+fn test_span_utilities() {
+    let a = Span::new(2, 5);
+    assert!(a < Span::new(3, 4) && a < Span::new(2, 6) && Span::new(0, 10) < a);
+    assert!(a.contains(2) && a.contains(4) && !a.contains(5));
+    assert!(a.contains_span(Span::new(3, 5)) && a.contains_span(a) && !a.contains_span(Span::new(1, 3)));
+    assert!(a.overlaps(Span::new(4, 8)) && !a.overlaps(Span::new(5, 8)) && !a.overlaps(Span::new(0, 2)));
+    assert_eq!(a.join(Span::new(7, 9)), Span::new(2, 9));
+    assert!(Span::new(3, 3).is_empty() && !a.is_empty());
+}
@@ -1,11 +1,19 @@
 use num::BigInt;
 
+use super::atom::Atom;
+use super::keyword::Keyword;
+use super::span::Span;
+
 #[derive(Debug, Clone)]
-pub(crate) enum TokenType {
+pub enum TokenType {
     //Special tokens
     
-    /// Any variable or property name
-    Identifier(String),
+    /// Any variable or property name, including contextual keywords such as `let`
+    Identifier(Atom),
+    /// A reserved word, such as `if` or `typeof`
+    Keyword(Keyword),
+    /// A class private name such as `#field`, without the `#`
+    PrivateIdentifier(Atom),
     /// `;`
     Semicolon,
     /// A new line
@@ -16,6 +24,8 @@ pub(crate) enum TokenType {
     OperatorDot,
     /// `?`
     OperatorQuestionMark,
+    /// `?.`
+    OperatorOptionalChaining,
     /// `:`
     OperatorColon,
     /// '...'
@@ -82,6 +92,8 @@ pub(crate) enum TokenType {
     OperatorLogicalAnd,
     /// `!`
     OperatorLogicalNot,
+    /// `??`
+    OperatorNullishCoalescing,
 
     // Assignment operators
 
@@ -140,108 +152,292 @@ pub(crate) enum TokenType {
     // Value literals
 
     /// A string literal, enclosed in double or single quotes
-    StringLiteral(String),
+    StringLiteral(Box<str>),
+    /// A template literal with no substitutions, e.g. `` `abc` ``
+    TemplateNoSubstitution(Box<str>),
+    /// The start of a template literal, up to the first substitution, e.g. `` `abc${ ``
+    TemplateHead(Box<str>),
+    /// The part of a template literal between two substitutions, e.g. `}abc${`
+    TemplateMiddle(Box<str>),
+    /// The end of a template literal, after the last substitution, e.g. `` }abc` ``
+    TemplateTail(Box<str>),
     /// A numeric literal with a decimal
     NumberLiteral(f64),
-    /// A numeric literal with no decimal
-    BigIntLiteral(BigInt),
+    /// A numeric literal with no decimal.
+    /// This is boxed as it is rare, and `BigInt` would make every token larger.
+    BigIntLiteral(Box<BigInt>),
+    /// A regular expression literal, e.g. `/ab+c/gi`.
+    /// This is boxed as it is rare, and holds two strings.
+    RegExpLiteral(Box<RegExp>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The parts of a regular expression literal
+pub struct RegExp {
+    /// The text between the slashes
+    pub pattern: String,
+    /// The flags after the closing slash
+    pub flags: String,
+}
+
+impl TokenType {
+    /// Whether the token is the given keyword.  
+    /// Contextual keywords are lexed as identifiers, so this also checks identifiers.
+    pub fn is_keyword(&self, keyword: Keyword) -> bool {
+        match self {
+            TokenType::Keyword(k) => *k == keyword,
+            TokenType::Identifier(i) => keyword.is_contextual() && i == keyword.as_str(),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub(crate) struct Token {
+pub struct Token {
     pub line: usize,
+    /// The column, counted in chars
     pub char: usize,
-    pub index: usize,
+    /// The bytes of the program the token was lexed from
+    pub span: Span,
     pub token: TokenType
 }
 
 impl Token {
     #[inline]
-    pub const fn new(line: usize, line_index: usize, token_start: usize, t: TokenType) -> Token {
-        Token { line, char: token_start - line_index + 1, index: token_start, token: t }
+    pub const fn new(line: usize, char: usize, span: Span, t: TokenType) -> Token {
+        Token { line, char, span, token: t }
     }
 }
 
-/// A map of strings to operators
-pub(crate) const OPERATORS: [(&'static str, TokenType); 55] = [
-    ("(", TokenType::OpenParen),
-    (")", TokenType::CloseParen),
-    ("[", TokenType::OpenSquareBracket),
-    ("]", TokenType::CloseSquareBracket),
-    ("{", TokenType::OpenBrace),
-    ("}", TokenType::CloseBrace),
-
-    ("...", TokenType::OperatorSpread),
-
-    (",", TokenType::Comma),
-    (".", TokenType::OperatorDot),
-    (";", TokenType::Semicolon),
-    ("=>", TokenType::OperatorFatArrow),
-
-    ("!==", TokenType::OperatorStrictInequality),
-    ("!=", TokenType::OperatorInequality),
-    ("===", TokenType::OperatorStrictEquality),
-    ("==", TokenType::OperatorEquality),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The two kinds of comment
+pub enum CommentKind {
+    /// A comment starting with `//`, or one of the HTML-like comments `<!--` and `-->`, which also end at the end of the line
+    Line,
+    /// A comment enclosed in `/*` and `*/`
+    Block,
+}
+
+#[derive(Debug, Clone)]
+/// A comment, collected when `Config::collect_comments` is set.  
+/// Comments are kept in a side table rather than on tokens, and refer to the token they are attached to by its index
+/// in the `PublicToken`s from `Engine::tokenize`.
+pub struct Comment {
+    pub line: usize,
+    pub char: usize,
+    /// The bytes of the program the comment was lexed from, including the `//`, `/*`, `*/`, `<!--`, or `-->`
+    pub span: Span,
+    pub kind: CommentKind,
+    /// The text of the comment, without the `//`, `/*`, `*/`, `<!--`, or `-->`
+    pub text: String,
+    /// The index of the token the comment is attached to.
+    /// For a leading comment at the end of the program, this is the number of tokens.
+    pub token: usize,
+    /// Whether the comment is after its token on the same line, rather than before it
+    pub trailing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The goal symbol the lexer is using, which decides what a `/` means.  
+/// The spec calls these `InputElementDiv` and `InputElementRegExp`.
+/// 
+/// Which goal is used depends on the previous significant token (ignoring newlines):
+/// * After something that ends an expression (an identifier, a literal, `this`, `)`, `]`, `++`, or `--`),
+///   `/` is division and `/=` is division assignment.
+/// * Anywhere else, such as after an operator, `(`, or at the start of a program, `/` starts a regex literal.
+/// * Other keywords, such as `return` and `typeof`, are treated like operators,
+///   unless they follow a `.` and so are property names.
+/// * Contextual keywords are identifiers, so `of / 2` and `await / 2` are division.
+///   Whether `await` and `yield` are keywords depends on the enclosing function, which the lexer can't know.
+///   The `of` in the head of a `for` is followed by an expression, which the lexer handles with the same paren tracking
+///   as the next rule.
+/// * `}` is ambiguous, as it could end an object literal or a block. It is treated as ending a block.
+/// * The `)` ending the condition of an `if`, `while`, `for`, or `with` is followed by a statement,
+///   so the lexer uses `RegExp` there. This needs paren matching, so is not handled by `LexGoal::after`.
+pub enum LexGoal {
+    /// `/` is a division operator
+    Div,
+    /// `/` starts a regex literal
+    RegExp,
+}
+
+impl Keyword {
+    /// Whether an expression (and so a regex literal) can start after this keyword
+    fn precedes_expression(&self) -> bool {
+        matches!(self,
+            Keyword::Return | Keyword::Typeof | Keyword::Instanceof | Keyword::In | Keyword::New
+            | Keyword::Delete | Keyword::Void | Keyword::Throw | Keyword::Case | Keyword::Do | Keyword::Else
+            | Keyword::Yield | Keyword::Await | Keyword::Extends
+        )
+    }
+}
+
+impl LexGoal {
+    /// Gets the goal to use for the token after `previous`, the last significant token.  
+    /// `after_dot` is whether `previous` itself came after a `.`. At the start of a program, the goal is `RegExp`.
+    pub fn after(previous: &TokenType, after_dot: bool) -> LexGoal {
+        // Contextual keywords are lexed as identifiers, so aren't included here
+        let keyword = match previous {
+            TokenType::Keyword(k) => Some(*k),
+            _ => None,
+        };
+
+        match (previous, keyword) {
+            // A keyword used as a property name e.g. `a.return / 2`
+            (_, Some(_)) if after_dot => LexGoal::Div,
+            (_, Some(k)) if k.precedes_expression() => LexGoal::RegExp,
+            (TokenType::Keyword(Keyword::This | Keyword::Super | Keyword::True | Keyword::False | Keyword::Null), _) => LexGoal::Div,
+            (TokenType::Keyword(_), _) => LexGoal::RegExp,
+
+            (TokenType::Identifier(_), _)
+            | (TokenType::PrivateIdentifier(_), _)
+            | (TokenType::StringLiteral(_), _)
+            | (TokenType::TemplateNoSubstitution(_), _)
+            | (TokenType::TemplateTail(_), _)
+            | (TokenType::NumberLiteral(_), _)
+            | (TokenType::BigIntLiteral(_), _)
+            | (TokenType::RegExpLiteral(_), _)
+            | (TokenType::CloseParen, _)
+            | (TokenType::CloseSquareBracket, _)
+            | (TokenType::OperatorIncrement, _)
+            | (TokenType::OperatorDecrement, _) => LexGoal::Div,
+            _ => LexGoal::RegExp,
+        }
+    }
+}
+
+/// Declares the table of operator strings, generating both `OPERATORS` and `TokenType::to_str`.  
+/// This means the lexer and anything rendering tokens can never disagree about an operator's text.
+macro_rules! operators {
+    ($($string:literal => $token:ident,)*) => {
+        /// A map of strings to operators.
+        /// Operators must come before any other operators they start with, as the first match is used.
+        pub(crate) const OPERATORS: &[(&str, TokenType)] = &[$(($string, TokenType::$token),)*];
+
+        impl TokenType {
+            /// Gets the source text of an operator or punctuation token, or `None` for any other token
+            pub fn to_str(&self) -> Option<&'static str> {
+                match self {
+                    $(TokenType::$token => Some($string),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+operators! {
+    "(" => OpenParen,
+    ")" => CloseParen,
+    "[" => OpenSquareBracket,
+    "]" => CloseSquareBracket,
+    "{" => OpenBrace,
+    "}" => CloseBrace,
+
+    "..." => OperatorSpread,
+
+    "," => Comma,
+    "." => OperatorDot,
+    ";" => Semicolon,
+    "=>" => OperatorFatArrow,
+
+    "!==" => OperatorStrictInequality,
+    "!=" => OperatorInequality,
+    "===" => OperatorStrictEquality,
+    "==" => OperatorEquality,
     
-    ("=", TokenType::OperatorAssignment),
-    ("+=", TokenType::OperatorAdditionAssignment),
-    ("-=", TokenType::OperatorSubtractionAssignment),
-    ("*=", TokenType::OperatorMultiplicationAssignment),
-    ("**=", TokenType::OperatorExponentiationAssignment),
-    ("/=", TokenType::OperatorDivisionAssignment),
-    ("%=", TokenType::OperatorRemainderAssignment),
-
-    ("||=", TokenType::OperatorLogicalOrAssignment),
-    ("&&=", TokenType::OperatorLogicalAndAssignment),
-    ("|=", TokenType::OperatorBitwiseOrAssignment),
-    ("&=", TokenType::OperatorBitwiseAndAssignment),
-    ("^=", TokenType::OperatorBitwiseXorAssignment),
-    ("??=", TokenType::OperatorNullishCoalescingAssignment),
-
-    ("<<=", TokenType::OperatorShiftLeftAssignment),
-    (">>=", TokenType::OperatorShiftRightAssignment),
-    (">>>=", TokenType::OperatorUnsignedShiftRightAssignment),
-
-    ("++", TokenType::OperatorIncrement),
-    ("--", TokenType::OperatorDecrement),
-    ("**", TokenType::OperatorExponentiation),
-
-    ("+", TokenType::OperatorAddition),
-    ("-", TokenType::OperatorSubtraction),
-    ("*", TokenType::OperatorMultiplication),
-    ("/", TokenType::OperatorDivision),
-    ("%", TokenType::OperatorRemainder),
-
-    ("||", TokenType::OperatorLogicalOr),
-    ("&&", TokenType::OperatorLogicalAnd),
-    ("!", TokenType::OperatorLogicalNot),
-    ("|", TokenType::OperatorBitwiseOr),
-    ("&", TokenType::OperatorBitwiseAnd),
-    ("^", TokenType::OperatorBitwiseXor),
-    ("~", TokenType::OperatorBitwiseNot),
-
-    ("<<", TokenType::OperatorShiftLeft),
-    (">>>", TokenType::OperatorUnsignedShiftRight),
-    (">>", TokenType::OperatorShiftRight),
-
-    ("<=", TokenType::OperatorLessThanOrEqual),
-    (">=", TokenType::OperatorGreaterThanOrEqual),
-    ("<", TokenType::OperatorLessThan),
-    (">", TokenType::OperatorGreaterThan),
-
-    ("?", TokenType::OperatorQuestionMark),
-    (":", TokenType::OperatorColon),
-];
+    "=" => OperatorAssignment,
+    "+=" => OperatorAdditionAssignment,
+    "-=" => OperatorSubtractionAssignment,
+    "*=" => OperatorMultiplicationAssignment,
+    "**=" => OperatorExponentiationAssignment,
+    "/=" => OperatorDivisionAssignment,
+    "%=" => OperatorRemainderAssignment,
+
+    "||=" => OperatorLogicalOrAssignment,
+    "&&=" => OperatorLogicalAndAssignment,
+    "|=" => OperatorBitwiseOrAssignment,
+    "&=" => OperatorBitwiseAndAssignment,
+    "^=" => OperatorBitwiseXorAssignment,
+    "??=" => OperatorNullishCoalescingAssignment,
+    "??" => OperatorNullishCoalescing,
+
+    "<<=" => OperatorShiftLeftAssignment,
+    ">>=" => OperatorShiftRightAssignment,
+    ">>>=" => OperatorUnsignedShiftRightAssignment,
+
+    "++" => OperatorIncrement,
+    "--" => OperatorDecrement,
+    "**" => OperatorExponentiation,
+
+    "+" => OperatorAddition,
+    "-" => OperatorSubtraction,
+    "*" => OperatorMultiplication,
+    "/" => OperatorDivision,
+    "%" => OperatorRemainder,
+
+    "||" => OperatorLogicalOr,
+    "&&" => OperatorLogicalAnd,
+    "!" => OperatorLogicalNot,
+    "|" => OperatorBitwiseOr,
+    "&" => OperatorBitwiseAnd,
+    "^" => OperatorBitwiseXor,
+    "~" => OperatorBitwiseNot,
+
+    "<<" => OperatorShiftLeft,
+    ">>>" => OperatorUnsignedShiftRight,
+    ">>" => OperatorShiftRight,
+
+    "<=" => OperatorLessThanOrEqual,
+    ">=" => OperatorGreaterThanOrEqual,
+    "<" => OperatorLessThan,
+    ">" => OperatorGreaterThan,
+
+    // Not used when followed by a digit, so that `a?.5:b` is a conditional
+    "?." => OperatorOptionalChaining,
+    "?" => OperatorQuestionMark,
+    ":" => OperatorColon,
+}
 
 #[test]
 /// Tests that no item in OPERATORS starts with an item before it in the array
 fn test_operator_ordering() {
-    for i in 0..OPERATORS.len() {
-        for j in i + 1..OPERATORS.len() {
-            if OPERATORS[j].0.starts_with(OPERATORS[i].0) {
-                panic!("Item '{}' at index {j} starts with item '{}' at index {i}", OPERATORS[j].0, OPERATORS[i].0)
+    for (i, (earlier, _)) in OPERATORS.iter().enumerate() {
+        for (j, (later, _)) in OPERATORS.iter().enumerate().skip(i + 1) {
+            if later.starts_with(earlier) {
+                panic!("Item '{later}' at index {j} starts with item '{earlier}' at index {i}")
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+/// Tests that lexing each string in OPERATORS gives its token, and that `to_str` gives back the string
+fn test_operator_round_trip() {
+    use super::Lexer;
+
+    for (string, token) in OPERATORS {
+        assert_eq!(token.to_str(), Some(*string));
+
+        // Lex after an identifier so that '/' and '/=' are not regex literals
+        let tokens = Lexer::default().lex(&format!("a {string}")).unwrap();
+        assert_eq!(tokens.len(), 2, "'{string}' lexed as {tokens:?}");
+        assert_eq!(std::mem::discriminant(&tokens[1].token), std::mem::discriminant(token), "'{string}' lexed as {tokens:?}");
+        assert_eq!(tokens[1].token.to_str(), Some(*string));
+    }
+
+    // `?.` followed by a digit is `?` and a number
+    let tokens = Lexer::default().lex("a?.5:b").unwrap();
+    let strings: Vec<_> = tokens.iter().map(|t| t.token.to_str()).collect();
+    assert_eq!(strings, [None, Some("?"), None, Some(":"), None]);
+    let tokens = Lexer::default().lex("a ?? b?.c").unwrap();
+    let strings: Vec<_> = tokens.iter().map(|t| t.token.to_str()).collect();
+    assert_eq!(strings, [None, Some("??"), None, Some("?."), None]);
+}
+
+#[test]
+/// Tests that large payloads are boxed, so that tokens stay small
+fn test_token_size() {
+    // The largest payloads are `Box<str>` and `Atom`, which are two words
+    assert!(std::mem::size_of::<TokenType>() <= 3 * std::mem::size_of::<usize>());
+}
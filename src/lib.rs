@@ -0,0 +1,12 @@
+//! A JavaScript engine.
+//!
+//! Most of the engine is used through `Engine`. The lexer can also be used on its own through `tokenize`,
+//! for tools such as syntax highlighters.
+
+pub mod lexer;
+pub mod engine;
+mod util;
+#[cfg(test)]
+mod tests;
+
+pub use lexer::public::{tokenize, PublicToken, TokenKind};
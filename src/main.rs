@@ -1,26 +1,65 @@
-mod lexer;
-mod engine;
-mod util;
-
-use lexer::Lexer;
-use engine::Engine;
+use js::engine::{Config, Engine, EngineError};
+use js::engine::diagnostics::{render_excerpt, StderrSink};
+use js::engine::explain::Explanations;
+use js::engine::features::FEATURES;
 
 use std::env;
 use std::fs;
+use std::rc::Rc;
+
+/// The exit code used when the program has a syntax error
+const EXIT_SYNTAX_ERROR: i32 = 3;
+/// The exit code used when the program goes over one of the engine's limits
+const EXIT_RESOURCE_LIMIT: i32 = 4;
+
+/// Prints an error with an excerpt of the program, and exits
+fn exit_with_error(e: EngineError, program: &str, filepath: &str) -> ! {
+    match e.location() {
+        Some((line, char)) => eprintln!("{e}\n{}", render_excerpt(program, filepath, line, char)),
+        None => eprintln!("{e}"),
+    }
+    eprintln!("For more information about this error, try `js --explain {}`", e.code());
+    match e {
+        EngineError::Syntax(_) => std::process::exit(EXIT_SYNTAX_ERROR),
+        EngineError::ResourceLimit(_) => std::process::exit(EXIT_RESOURCE_LIMIT),
+    }
+}
 
 fn main() -> Result<(), String> {
     let args: Vec<String> = env::args().collect();
 
     let mut filepath: Option<String> = None;
+    // Whether to print the tokens instead of running the program
+    let mut print_tokens = false;
+    // Whether to print warnings to stderr
+    let mut print_warnings = false;
 
-    let mut args = args.iter();
     // First arg is path to binary, so ignore it
-    args.next();
-
-    while let Some(arg) = args.next() {
+    let mut arg_iter = args.iter().skip(1);
+    while let Some(arg) = arg_iter.next() {
         let mut char_iter = arg.chars();
         if char_iter.next().unwrap() == '-' {
             match char_iter.as_str() {
+                "-print-tokens" => print_tokens = true,
+                "-warnings" => print_warnings = true,
+                // Print which features are supported, then exit
+                "-features" => {
+                    for feature in FEATURES {
+                        println!("{feature}");
+                    }
+                    return Ok(());
+                }
+                // Print the explanation of an error code, then exit
+                "-explain" => {
+                    let Some(code) = arg_iter.next() else {
+                        return Err("Expected an error code after '--explain'".to_string());
+                    };
+                    let Some(explanation) = Explanations::default().get(code).map(str::to_string) else {
+                        return Err(format!("Unknown error code '{code}'"));
+                    };
+                    println!("{explanation}");
+                    return Ok(());
+                }
                 s => {
                     return Err(format!("Unknown flag '-{s}'"));
                 }
@@ -42,8 +81,23 @@ fn main() -> Result<(), String> {
         return Err(format!("File not found: '{filepath}'"));
     };
 
-    let mut engine = Engine::new();
-    engine.parse(&program);
+    let mut config = Config::default();
+    if print_warnings {
+        config.diagnostics = Some(Rc::new(StderrSink));
+    }
+    let mut engine = Engine::with_config(config);
+
+    if print_tokens {
+        let tokens = engine.tokenize(&program).unwrap_or_else(|e| exit_with_error(e, &program, &filepath));
+        for token in tokens {
+            println!("{token:?}")
+        }
+        return Ok(());
+    }
+
+    if let Err(e) = engine.parse(&program) {
+        exit_with_error(e, &program, &filepath);
+    }
 
     Ok(())
 }
@@ -0,0 +1,6 @@
+// A single line comment
+a /* inline */ b
+/*
+ * A multi line comment
+ */
+c // trailing
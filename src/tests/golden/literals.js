@@ -0,0 +1,6 @@
+"double" 'single' `back
+tick`
+"escapes \n\t\0 \101"
+0x1F 0o17 0b101 017 10 1_000
+0n 123n 0xFFn
+`a ${b + `c${d}`} e`
@@ -0,0 +1,6 @@
+a += b ** c >>> 2;
+x ??= y?.z ?? w;
+f(...args) => !a && b || ~c
+i++ --j
+a !== b === c
+a?.5:b ?? .5
@@ -0,0 +1,5 @@
+let r = /ab+c/gi;
+a / b / c
+s.replace(/[/]/g, "")
+if (x) return /=/.test(y)
+x.return / 2
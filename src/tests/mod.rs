@@ -0,0 +1,80 @@
+//! Golden-file tests.
+//!
+//! Each `.js` file in `src/tests/golden` is lexed, and the tokens are compared against the matching `.tokens` file.
+//! Run with the environment variable `BLESS=1` to regenerate the `.tokens` files after an intended change.
+
+use std::fs;
+use std::path::Path;
+
+use crate::lexer::Lexer;
+
+/// The directory holding the golden files
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/golden");
+
+/// Gets the output to compare for a program: one token per line, or the error
+fn dump_tokens(program: &str) -> String {
+    match Lexer::default().lex(program) {
+        Ok(tokens) => tokens.iter().map(|t| format!("{t:?}\n")).collect(),
+        Err(e) => format!("{e:?}\n"),
+    }
+}
+
+/// Gets a readable diff of two dumps, listing only the lines which differ
+fn diff(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut diff = String::new();
+
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => break,
+            (e, a) if e == a => (),
+            (e, a) => {
+                if let Some(e) = e {
+                    diff += &format!("{line:>4} - {e}\n");
+                }
+                if let Some(a) = a {
+                    diff += &format!("{line:>4} + {a}\n");
+                }
+            }
+        }
+        line += 1;
+    }
+    diff
+}
+
+#[test]
+/// Tests every program in the golden directory against its expected output
+fn test_golden_files() {
+    let bless = std::env::var_os("BLESS").is_some();
+    let mut failures = vec![];
+
+    let mut paths: Vec<_> = fs::read_dir(GOLDEN_DIR).expect("Golden directory should exist")
+        .map(|entry| entry.expect("Should have been able to read directory entry").path())
+        .filter(|path| path.extension().is_some_and(|e| e == "js"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "No golden files found in {GOLDEN_DIR}");
+
+    for path in paths {
+        let program = fs::read_to_string(&path).expect("Should have been able to read program");
+        let actual = dump_tokens(&program);
+        let expected_path = path.with_extension("tokens");
+
+        if bless {
+            fs::write(&expected_path, &actual).expect("Should have been able to write expected output");
+            continue;
+        }
+
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if expected != actual {
+            let name = Path::new(&path).file_name().unwrap().to_string_lossy().to_string();
+            failures.push(format!("{name}:\n{}", diff(&expected, &actual)));
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!("Output changed for {} golden file(s). Rerun with BLESS=1 if this was intended.\n\n{}", failures.len(), failures.join("\n"));
+    }
+}
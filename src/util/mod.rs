@@ -9,4 +9,20 @@ pub fn is_identifier_start(c: char) -> bool {c == '$' || c == '_' || is_id_start
 
 /// Checks if a character is a valid not-first character of an identifier.  
 /// Simple wrapper around `unicode_id_start::is_id_continue()` to include '$' and '_'
-pub fn is_identifier_continue(c: char) -> bool {c == '$' || c == '_' || is_id_continue(c)}
\ No newline at end of file
+pub fn is_identifier_continue(c: char) -> bool {c == '$' || c == '_' || is_id_continue(c)}
+
+/// Checks if a character is in the spec's `WhiteSpace` production.  
+/// This is `<TAB>`, `<VT>`, `<FF>`, `<ZWNBSP>`, and any character in the unicode "Space_Separator" category
+pub fn is_whitespace(c: char) -> bool {
+    matches!(c,
+        '\u{0009}' | '\u{000B}' | '\u{000C}' | '\u{FEFF}' |
+        // Space_Separator (Zs)
+        '\u{0020}' | '\u{00A0}' | '\u{1680}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}'
+    )
+}
+
+/// Checks if a character is in the spec's `LineTerminator` production.  
+/// This is `<LF>`, `<CR>`, `<LS>`, and `<PS>`
+pub fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}